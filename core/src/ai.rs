@@ -0,0 +1,391 @@
+//ai.rs
+use rand::Rng;
+
+use crate::boss::boss_take_turn;
+use crate::color::*;
+use crate::faction::is_companion;
+use crate::game::*;
+use crate::map::Map;
+use crate::object::*;
+use crate::pathfinding::Path;
+use crate::rules::{flight_path, is_blocked, line_of_fire, line_of_sight, move_by, mut_two};
+use crate::status::{tick_statuses, StatusKind};
+use crate::PLAYER;
+
+// A fleeing monster gets a hit point back every few turns
+const FLEE_HEAL_INTERVAL: i32 = 2;
+// Companions catch up with the player when they are further away than that
+const FOLLOW_DISTANCE: f32 = 2.5;
+// Ranged monsters back off when an enemy gets closer than that
+const RANGED_KEEP_DISTANCE: f32 = 3.0;
+
+pub fn ai_take_turn(monster_id: usize, game: &mut Game, objects: &mut [Object]) {
+    wear_off_conversion(monster_id, game, objects);
+    if !objects[monster_id].has_status(StatusKind::Paralysed) {
+        act(monster_id, game, objects);
+    }
+    if objects[monster_id].alive {
+        tick_statuses(monster_id, game, objects);
+    }
+    if let Some(senses) = objects[monster_id].senses.as_mut() {
+        senses.last_turn = game.clock;
+    }
+}
+
+// One action, a confused monster forgets all about its plans
+fn act(monster_id: usize, game: &mut Game, objects: &mut [Object]) {
+    if objects[monster_id].has_status(StatusKind::Confused) {
+        let dx = game.rng.gen_range(-1, 2);
+        let dy = game.rng.gen_range(-1, 2);
+        move_by(monster_id, dx, dy, &game.map, objects);
+        return;
+    }
+    if boss_take_turn(monster_id, game, objects) {
+        return;
+    }
+    if let Some(ai) = objects[monster_id].ai.take() {
+        let new_ai = match ai {
+            Ai::Basic | Ai::Ranged { .. } if objects[monster_id].fighter.is_some_and(|f| f.is_broken()) => {
+                if game.fov.is_in_fov(objects[monster_id].x, objects[monster_id].y) {
+                    game.messages.add(
+                        format!("The {} breaks and flees!", objects[monster_id].name),
+                        LIGHT_GREEN,
+                    );
+                }
+                ai_fleeing(monster_id, game, objects, Box::new(ai), 0)
+            }
+            Ai::Basic => ai_basic(monster_id, game, objects),
+            Ai::Ranged { range, accuracy } => ai_ranged(monster_id, game, objects, range, accuracy),
+            Ai::Fleeing {
+                previous_ai,
+                num_turns,
+            } => ai_fleeing(monster_id, game, objects, previous_ai, num_turns),
+        };
+        objects[monster_id].ai = Some(new_ai);
+    }
+
+}
+
+// Magic does not last, back to its old friends
+fn wear_off_conversion(monster_id: usize, game: &mut Game, objects: &mut [Object]) {
+    let monster = &mut objects[monster_id];
+    if let Some(mut conversion) = monster.conversion.take() {
+        conversion.num_turns -= 1;
+        if conversion.num_turns > 0 {
+            monster.conversion = Some(conversion);
+        } else {
+            monster.faction = conversion.previous_faction;
+            if game.fov.is_in_fov(monster.x, monster.y) {
+                game.messages.add(format!("The {} comes back to its senses", monster.name), ORANGE);
+            }
+        }
+    }
+}
+
+fn move_towards(id: usize, target_x: i32, target_y: i32, map: &Map, objects: &mut [Object]) {
+    let dx = target_x - objects[id].x;
+    let dy = target_y - objects[id].y;
+    let distance = ((dx.pow(2) + dy.pow(2)) as f32).sqrt();
+    let dx = (dx as f32 / distance).round() as i32;
+    let dy = (dy as f32 / distance).round() as i32;
+    move_by(id, dx, dy, map, objects);
+}
+
+// Follow the cached path to the goal, finding a new one when it went stale.
+// Straight line when there is no way at all.
+fn move_along_path(id: usize, goal: (i32, i32), map: &Map, objects: &mut [Object]) {
+    let from = objects[id].pos();
+    let is_valid = objects[id]
+        .path
+        .as_ref()
+        .is_some_and(|path| path.is_valid(from, goal, objects));
+    if !is_valid {
+        objects[id].path = Path::find(from, goal, map, objects);
+    }
+    match objects[id].path.as_ref().and_then(|path| path.next_step()) {
+        Some((x, y)) => {
+            // someone in the way, wait for them to move on
+            if !is_blocked(x, y, map, objects) {
+                objects[id].set_pos(x, y);
+                if let Some(path) = objects[id].path.as_mut() {
+                    path.advance();
+                }
+            }
+        }
+        None => move_towards(id, goal.0, goal.1, map, objects),
+    }
+}
+
+// The closest creature it is hostile to and can see with its own eyes:
+// close enough and nothing in the way, whatever the player sees
+pub(crate) fn visible_enemy(monster_id: usize, game: &Game, objects: &[Object]) -> Option<usize> {
+    let sight_radius = objects[monster_id].senses?.sight_radius;
+    if objects[monster_id].has_status(StatusKind::Blinded) {
+        return None;
+    }
+    let monster = &objects[monster_id];
+    let (monster_x, monster_y) = monster.pos();
+    objects
+        .iter()
+        .enumerate()
+        .filter(|&(id, other)| {
+            id != monster_id
+                && other.alive
+                && other.fighter.is_some()
+                && game.data.are_hostile(monster, other)
+                && monster.distance_to(other) <= sight_radius as f32
+                && line_of_sight(monster.pos(), other.pos(), &game.map)
+        })
+        .min_by_key(|&(_, other)| (other.x - monster_x).pow(2) + (other.y - monster_y).pow(2))
+        .map(|(id, _)| id)
+}
+
+// Look and listen, then sleep, wander or search when no enemy is in sight.
+// The enemy to deal with when the monster can see one.
+fn perceive(monster_id: usize, game: &mut Game, objects: &mut [Object]) -> Option<usize> {
+    let mut senses = objects[monster_id].senses?;
+    let monster_pos = objects[monster_id].pos();
+    let (x, y) = monster_pos;
+    let noise = game
+        .noises
+        .iter()
+        .filter(|noise| noise.made_at >= senses.last_turn)
+        .map(|noise| noise.pos)
+        .find(|&(x, y)| (x - monster_pos.0).pow(2) + (y - monster_pos.1).pow(2) <= senses.hearing.pow(2));
+    let in_view = game.fov.is_in_fov(monster_pos.0, monster_pos.1);
+    let name = objects[monster_id].name.clone();
+
+    if let (Awareness::Asleep, Some(noise)) = (senses.awareness, noise) {
+        if in_view {
+            game.messages.add(format!("The {} wakes up!", name), ORANGE);
+        }
+        senses.awareness = Awareness::Hunting { last_known: noise };
+    }
+    let enemy_id = match senses.awareness {
+        Awareness::Asleep => None,
+        _ => visible_enemy(monster_id, game, objects),
+    };
+    senses.awareness = match (senses.awareness, noise, enemy_id) {
+        (Awareness::Asleep, _, _) => Awareness::Asleep,
+        (awareness, _, Some(enemy_id)) => {
+            if enemy_id == PLAYER && in_view && !matches!(awareness, Awareness::Hunting { .. }) {
+                game.messages.add(format!("The {} spots you!", name), ORANGE);
+            }
+            Awareness::Hunting {
+                last_known: objects[enemy_id].pos(),
+            }
+        }
+        (_, Some(noise), None) => Awareness::Hunting { last_known: noise },
+        (awareness, None, None) => awareness,
+    };
+
+    match senses.awareness {
+        _ if enemy_id.is_some() => {}
+        Awareness::Asleep => {}
+        // companions stick with the player rather than go looking for trouble
+        _ if is_companion(&objects[monster_id]) => {
+            senses.awareness = Awareness::Wandering;
+            if objects[monster_id].distance_to(&objects[PLAYER]) > FOLLOW_DISTANCE {
+                let player_pos = objects[PLAYER].pos();
+                move_along_path(monster_id, player_pos, &game.map, objects);
+            }
+        }
+        Awareness::Wandering if senses.greedy && game.ai_maps.to_items.value(x, y).is_some() => {
+            // on the loot already it stays put
+            if let Some((next_x, next_y)) = game.ai_maps.to_items.downhill(x, y, &game.map, objects) {
+                objects[monster_id].set_pos(next_x, next_y);
+            }
+        }
+        Awareness::Wandering => {
+            let dx = game.rng.gen_range(-1, 2);
+            let dy = game.rng.gen_range(-1, 2);
+            move_by(monster_id, dx, dy, &game.map, objects);
+        }
+        Awareness::Hunting { last_known } => {
+            let arrived = monster_pos == last_known
+                || (objects[monster_id].distance(last_known.0, last_known.1) < 2.0
+                    && is_blocked(last_known.0, last_known.1, &game.map, objects));
+            if arrived {
+                // nobody there anymore, look around
+                senses.awareness = Awareness::Wandering;
+            } else {
+                move_along_path(monster_id, last_known, &game.map, objects);
+            }
+        }
+    }
+    objects[monster_id].senses = Some(senses);
+    enemy_id
+}
+
+// Down the shared map when going for the player, its own path for anybody
+// else or when the way down is blocked
+fn chase(monster_id: usize, enemy_id: usize, game: &Game, objects: &mut [Object]) {
+    let (x, y) = objects[monster_id].pos();
+    if enemy_id == PLAYER {
+        if let Some((next_x, next_y)) = game.ai_maps.to_player.downhill(x, y, &game.map, objects) {
+            objects[monster_id].set_pos(next_x, next_y);
+            return;
+        }
+    }
+    let enemy_pos = objects[enemy_id].pos();
+    move_along_path(monster_id, enemy_pos, &game.map, objects);
+}
+
+fn ai_basic(monster_id: usize, game: &mut Game, objects: &mut [Object]) -> Ai {
+    if let Some(enemy_id) = perceive(monster_id, game, objects) {
+        if objects[monster_id].distance_to(&objects[enemy_id]) >= 2.0 {
+            chase(monster_id, enemy_id, game, objects);
+        } else {
+            let (monster, enemy) = mut_two(monster_id, enemy_id, objects);
+            monster.attack(enemy, game);
+        }
+    }
+    Ai::Basic
+}
+
+// Shoot when the enemy is in range with a clear line, step back when they
+// come too close, get closer otherwise
+fn ai_ranged(monster_id: usize, game: &mut Game, objects: &mut [Object], range: i32, accuracy: i32) -> Ai {
+    let (monster_x, monster_y) = objects[monster_id].pos();
+    if let Some(enemy_id) = perceive(monster_id, game, objects) {
+        let enemy_pos = objects[enemy_id].pos();
+        let distance = objects[monster_id].distance_to(&objects[enemy_id]);
+        let back_off = if distance < RANGED_KEEP_DISTANCE {
+            step_away(monster_id, enemy_id, game, objects)
+        } else {
+            None
+        };
+        if let Some((x, y)) = back_off {
+            objects[monster_id].set_pos(x, y);
+        } else if distance <= range as f32
+            && line_of_fire((monster_x, monster_y), enemy_pos, range, &game.map)
+        {
+            shoot(monster_id, enemy_pos, range, accuracy, game, objects);
+        } else {
+            chase(monster_id, enemy_id, game, objects);
+        }
+    }
+    Ai::Ranged { range, accuracy }
+}
+
+// Where to step to get away from the enemy. Down the flee map for the
+// player, just further away for anybody else
+fn step_away(monster_id: usize, enemy_id: usize, game: &Game, objects: &[Object]) -> Option<(i32, i32)> {
+    let (x, y) = objects[monster_id].pos();
+    if enemy_id == PLAYER {
+        return game.ai_maps.flee_player.downhill(x, y, &game.map, objects);
+    }
+    let distance = |(x, y): (i32, i32)| objects[enemy_id].distance(x, y);
+    let here = distance((x, y));
+    game.map
+        .neighbours(x, y)
+        .filter(|&(next_x, next_y)| !is_blocked(next_x, next_y, &game.map, objects))
+        .filter(|&pos| distance(pos) > here)
+        .max_by(|&first, &second| distance(first).total_cmp(&distance(second)))
+}
+
+// The shot flies on until it hits someone or something, each creature on
+// the way can be hit, the one it was aimed at or not. The player only hears
+// about the shots they see.
+fn shoot(shooter_id: usize, target: (i32, i32), range: i32, accuracy: i32, game: &mut Game, objects: &mut [Object]) {
+    let from = objects[shooter_id].pos();
+    if game.fov.is_in_fov(from.0, from.1) || game.fov.is_in_fov(target.0, target.1) {
+        game.messages.add(format!("The {} shoots!", objects[shooter_id].name), WHITE);
+    }
+    for (x, y) in flight_path(from, target, range) {
+        if game.map.blocks_sight(x, y) {
+            return;
+        }
+        let victim_id = objects
+            .iter()
+            .position(|object| object.fighter.is_some() && object.pos() == (x, y));
+        if let Some(victim_id) = victim_id {
+            if game.rng.gen_range(0, 100) < accuracy {
+                let (shooter, victim) = mut_two(shooter_id, victim_id, objects);
+                shooter.attack(victim, game);
+                return;
+            }
+            if game.fov.is_in_fov(from.0, from.1) || game.fov.is_in_fov(x, y) {
+                game.messages.add(format!("The shot misses {}", objects[victim_id].name), WHITE);
+            }
+        }
+    }
+}
+
+// The closest friend still up for a fight: same faction, not running away
+fn closest_friend(monster_id: usize, objects: &[Object]) -> Option<usize> {
+    let monster = &objects[monster_id];
+    objects
+        .iter()
+        .enumerate()
+        .filter(|&(id, other)| {
+            id != monster_id
+                && id != PLAYER
+                && other.alive
+                && other.faction.is_some()
+                && other.faction == monster.faction
+                && matches!(other.ai, Some(Ai::Basic) | Some(Ai::Ranged { .. }))
+        })
+        .min_by(|&(_, first), &(_, second)| monster.distance_to(first).total_cmp(&monster.distance_to(second)))
+        .map(|(id, _)| id)
+}
+
+// Run away, licking its wounds, and regroup with its friends once out of
+// sight, or make for the stairs when it has none. Back to the fight once
+// healed, fights back when an enemy has it backed into a corner.
+fn ai_fleeing(
+    monster_id: usize,
+    game: &mut Game,
+    objects: &mut [Object],
+    previous_ai: Box<Ai>,
+    num_turns: i32,
+    ) -> Ai {
+    if num_turns % FLEE_HEAL_INTERVAL == FLEE_HEAL_INTERVAL - 1 {
+        objects[monster_id].heal(1, game);
+    }
+    let in_view = game.fov.is_in_fov(objects[monster_id].x, objects[monster_id].y);
+    if objects[monster_id].fighter.is_some_and(|f| f.hp >= f.max_hp) {
+        if in_view {
+            game.messages.add(
+                format!("The {} rallies and comes back for more!", objects[monster_id].name),
+                ORANGE,
+            );
+        }
+        return *previous_ai;
+    }
+
+    match visible_enemy(monster_id, game, objects) {
+        Some(enemy_id) => match step_away(monster_id, enemy_id, game, objects) {
+            Some((x, y)) => objects[monster_id].set_pos(x, y),
+            None if objects[monster_id].distance_to(&objects[enemy_id]) < 2.0 => {
+                if in_view {
+                    game.messages.add(
+                        format!("The cornered {} fights back!", objects[monster_id].name),
+                        ORANGE,
+                    );
+                }
+                let (monster, enemy) = mut_two(monster_id, enemy_id, objects);
+                monster.attack(enemy, game);
+            }
+            None => {}
+        },
+        None => match closest_friend(monster_id, objects) {
+            Some(friend_id) => {
+                if objects[monster_id].distance_to(&objects[friend_id]) >= 2.0 {
+                    let friend_pos = objects[friend_id].pos();
+                    move_along_path(monster_id, friend_pos, &game.map, objects);
+                }
+            }
+            None => {
+                let (x, y) = objects[monster_id].pos();
+                if let Some((next_x, next_y)) = game.ai_maps.to_stairs.downhill(x, y, &game.map, objects) {
+                    objects[monster_id].set_pos(next_x, next_y);
+                }
+            }
+        },
+    }
+    Ai::Fleeing {
+        previous_ai,
+        num_turns: num_turns + 1,
+    }
+}
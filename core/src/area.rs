@@ -0,0 +1,128 @@
+//area.rs
+// Effects that reach everything around an impact point: explosions and
+// splashes hit once, clouds linger on their tiles for a few turns. Walls shelter whoever
+// stands behind them, only the tiles seen from the impact point are reached.
+// Nobody is spared, the player included.
+use serde::{Deserialize, Serialize};
+
+use crate::color::*;
+use crate::fov::Fov;
+use crate::game::Game;
+use crate::map::Map;
+use crate::object::Object;
+use crate::status::StatusKind;
+use crate::PLAYER;
+
+// Tiles within `radius` of `center` with nothing in the way
+pub fn area(center: (i32, i32), radius: i32, map: &Map) -> Vec<(i32, i32)> {
+    let mut fov = Fov::new(map.width(), map.height());
+    fov.compute(map, center.0, center.1, radius, false);
+    map.positions().filter(|&(x, y)| fov.is_in_fov(x, y)).collect()
+}
+
+// The creatures standing in the area
+fn caught(tiles: &[(i32, i32)], objects: &[Object]) -> Vec<usize> {
+    objects
+        .iter()
+        .enumerate()
+        .filter(|(_, object)| object.alive && object.fighter.is_some() && tiles.contains(&object.pos()))
+        .map(|(id, _)| id)
+        .collect()
+}
+
+pub fn explode(center: (i32, i32), radius: i32, damage: i32, game: &mut Game, objects: &mut [Object]) {
+    let tiles = area(center, radius, &game.map);
+    for id in caught(&tiles, objects) {
+        if id == PLAYER {
+            game.messages.add(format!("You are burned for {} hit points", damage), ORANGE);
+        } else {
+            game.messages.add(
+                format!("The {} is burned for {} hit points", objects[id].name, damage),
+                ORANGE,
+            );
+        }
+        objects[id].take_damage(damage, game);
+    }
+    game.make_noise(center);
+}
+
+// A healing potion shattering does its good to everybody around
+pub fn splash_heal(center: (i32, i32), radius: i32, amount: i32, game: &mut Game, objects: &mut [Object]) {
+    let tiles = area(center, radius, &game.map);
+    for id in caught(&tiles, objects) {
+        if id == PLAYER {
+            game.messages.add("Your wounds start to feel better!", LIGHT_VIOLET);
+        } else if game.fov.is_in_fov(objects[id].x, objects[id].y) {
+            game.messages.add(format!("The {} looks better", objects[id].name), LIGHT_VIOLET);
+        }
+        objects[id].heal(amount, game);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Cloud {
+    pub name: String,
+    pub color: Color,
+    pub tiles: Vec<(i32, i32)>,
+    // given to whoever breathes it
+    pub status: StatusKind,
+    pub status_turns: i32,
+    // before it blows away
+    pub num_turns: i32,
+}
+
+impl Cloud {
+    pub fn covers(&self, x: i32, y: i32) -> bool {
+        self.tiles.contains(&(x, y))
+    }
+
+    // Everybody inside gets the status, the ones who did not have it yet
+    // are told so
+    fn engulf(&self, game: &mut Game, objects: &mut [Object]) {
+        for id in caught(&self.tiles, objects) {
+            let object = &mut objects[id];
+            if !object.has_status(self.status) {
+                if id == PLAYER {
+                    game.messages.add(
+                        format!("The {} leaves you {}", self.name, self.status.name()),
+                        LIGHT_VIOLET,
+                    );
+                } else if game.fov.is_in_fov(object.x, object.y) {
+                    game.messages.add(
+                        format!("The {} leaves the {} {}", self.name, object.name, self.status.name()),
+                        LIGHT_VIOLET,
+                    );
+                }
+            }
+            object.add_status(self.status, self.status_turns);
+        }
+    }
+}
+
+// A new cloud catches whoever is already there
+pub fn release(cloud: Cloud, game: &mut Game, objects: &mut [Object]) {
+    cloud.engulf(game, objects);
+    game.clouds.push(cloud);
+}
+
+// Once a turn: the clouds catch whoever walked in, then thin out
+pub fn tick_clouds(game: &mut Game, objects: &mut [Object]) {
+    let clouds = game.clouds.clone();
+    for cloud in &clouds {
+        cloud.engulf(game, objects);
+    }
+    for cloud in game.clouds.iter_mut() {
+        cloud.num_turns -= 1;
+    }
+    let in_view = |cloud: &Cloud| cloud.tiles.iter().any(|&(x, y)| game.fov.is_in_fov(x, y));
+    let gone: Vec<String> = game
+        .clouds
+        .iter()
+        .filter(|cloud| cloud.num_turns <= 0 && in_view(cloud))
+        .map(|cloud| format!("The {} blows away", cloud.name))
+        .collect();
+    for message in gone {
+        game.messages.add(message, LIGHT_GREY);
+    }
+    game.clouds.retain(|cloud| cloud.num_turns > 0);
+}
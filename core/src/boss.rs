@@ -0,0 +1,319 @@
+//boss.rs
+// Unique monsters read from data/bosses.json, each one waits on the stairs
+// down of its own dungeon level. A boss fights in phases: once its hit
+// points drop below the threshold of the next phase it switches to that
+// phase's stats and abilities. Its death sets off the effects of its entry.
+use serde::{Deserialize, Serialize};
+
+use crate::ai::visible_enemy;
+use crate::color::*;
+use crate::game::Game;
+use crate::map::Tile;
+use crate::object::*;
+use crate::rules::{is_blocked, line_of_sight};
+use crate::spawn::{validate_ai, ItemTemplate, MonsterTemplate};
+use crate::status::StatusKind;
+
+// One entry of bosses.json
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BossTemplate {
+    pub name: String,
+    pub glyph: char,
+    pub color: Color,
+    pub dungeon_level: u32,
+    pub max_hp: i32,
+    pub faction: String,
+    pub ai: Ai,
+    pub sight_radius: i32,
+    pub hearing: i32,
+    pub speed: i32,
+    // the first one is where the fight starts
+    pub phases: Vec<Phase>,
+    pub on_death: Vec<DeathEffect>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Phase {
+    // percent of max_hp, the phase starts when the hit points go below it
+    pub below_hp: i32,
+    // told when the phase starts, the first phase starts quietly
+    #[serde(default)]
+    pub message: Option<String>,
+    pub defense: i32,
+    pub power: i32,
+    pub abilities: Vec<Ability>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ability {
+    pub kind: AbilityKind,
+    // turns to wait before using it again
+    pub cooldown: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AbilityKind {
+    // the closest enemy in sight cannot move for a while
+    PetrifyingGaze { range: i32, num_turns: i32 },
+    // monsters from monsters.json show up around the boss
+    Summon { monster: String, count: i32 },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DeathEffect {
+    // items from items.json, left where the boss fell
+    DropLoot { items: Vec<String> },
+    // the walls around the boss come down
+    CollapseArena { radius: i32 },
+}
+
+// Kept on the boss between turns
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BossState {
+    pub name: String,
+    pub phase: usize,
+    // turns before each ability of the phase is ready
+    pub cooldowns: Vec<i32>,
+}
+
+impl BossTemplate {
+    // Summoned monsters and loot must be in the other data files
+    pub fn validate(&self, monsters: &[MonsterTemplate], items: &[ItemTemplate]) -> Result<(), String> {
+        if self.max_hp <= 0 {
+            return Err("max_hp must be positive".into());
+        }
+        if self.sight_radius < 1 || self.hearing < 0 {
+            return Err("sight_radius must be positive and hearing cannot be negative".into());
+        }
+        if self.speed <= 0 {
+            return Err("speed must be positive".into());
+        }
+        validate_ai(&self.ai)?;
+        match self.phases.first() {
+            Some(phase) if phase.below_hp == 100 => {}
+            _ => return Err("the first phase must start at below_hp 100".into()),
+        }
+        if self.phases.windows(2).any(|pair| pair[0].below_hp <= pair[1].below_hp) {
+            return Err("phases must start lower and lower".into());
+        }
+        for (index, phase) in self.phases.iter().enumerate() {
+            let phase_name = format!("phase #{}", index);
+            if phase.below_hp <= 0 {
+                return Err(format!("{}: below_hp must be positive", phase_name));
+            }
+            if phase.defense < 0 || phase.power < 0 {
+                return Err(format!("{}: defense and power cannot be negative", phase_name));
+            }
+            for ability in &phase.abilities {
+                if ability.cooldown < 0 {
+                    return Err(format!("{}: cooldown cannot be negative", phase_name));
+                }
+                match ability.kind {
+                    AbilityKind::PetrifyingGaze { range, num_turns } => {
+                        if range <= 0 || num_turns <= 0 {
+                            return Err(format!("{}: gaze parameters must be positive", phase_name));
+                        }
+                    }
+                    AbilityKind::Summon { ref monster, count } => {
+                        if count <= 0 {
+                            return Err(format!("{}: count must be positive", phase_name));
+                        }
+                        if !monsters.iter().any(|template| &template.name == monster) {
+                            return Err(format!("{}: no monster \"{}\" in monsters.json", phase_name, monster));
+                        }
+                    }
+                }
+            }
+        }
+        for effect in &self.on_death {
+            match *effect {
+                DeathEffect::DropLoot { items: ref loot } => {
+                    if let Some(name) = loot.iter().find(|name| !items.iter().any(|item| &item.name == *name)) {
+                        return Err(format!("on_death: no item \"{}\" in items.json", name));
+                    }
+                }
+                DeathEffect::CollapseArena { radius } => {
+                    if radius <= 0 {
+                        return Err("on_death: radius must be positive".into());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Asleep in its lair until the fighting wakes it up
+    pub fn spawn(&self, x: i32, y: i32) -> Object {
+        let phase = &self.phases[0];
+        let mut boss = Object::new(x, y, self.glyph, self.color, &self.name, true);
+        boss.alive = true;
+        boss.fighter = Some(Fighter {
+            max_hp: self.max_hp,
+            hp: self.max_hp,
+            defense: phase.defense,
+            power: phase.power,
+            morale: 100,
+            on_death: DeathCallBack::Boss,
+        });
+        boss.ai = Some(self.ai.clone());
+        boss.faction = Some(self.faction.clone());
+        boss.speed = self.speed;
+        boss.senses = Some(Senses {
+            sight_radius: self.sight_radius,
+            hearing: self.hearing,
+            awareness: Awareness::Asleep,
+            greedy: false,
+            last_turn: 0,
+        });
+        boss.boss = Some(BossState {
+            name: self.name.clone(),
+            phase: 0,
+            cooldowns: vec![0; phase.abilities.len()],
+        });
+        boss
+    }
+}
+
+// Move on to the next phase when hurt enough, then use the first ability
+// that is ready. True when that took the boss's turn.
+pub fn boss_take_turn(boss_id: usize, game: &mut Game, objects: &mut [Object]) -> bool {
+    let mut state = match objects[boss_id].boss.take() {
+        Some(state) => state,
+        None => return false,
+    };
+    let template = match game.data.boss(&state.name) {
+        Some(template) => template.clone(),
+        None => {
+            objects[boss_id].boss = Some(state);
+            return false;
+        }
+    };
+    if let Some(fighter) = objects[boss_id].fighter.as_mut() {
+        while let Some(next) = template.phases.get(state.phase + 1) {
+            if fighter.hp * 100 >= fighter.max_hp * next.below_hp {
+                break;
+            }
+            state.phase += 1;
+            state.cooldowns = vec![0; next.abilities.len()];
+            fighter.defense = next.defense;
+            fighter.power = next.power;
+            if let Some(ref message) = next.message {
+                game.messages.add(message.clone(), ORANGE);
+            }
+        }
+    }
+    for cooldown in state.cooldowns.iter_mut() {
+        *cooldown = (*cooldown - 1).max(0);
+    }
+
+    let asleep = objects[boss_id]
+        .senses
+        .is_none_or(|senses| senses.awareness == Awareness::Asleep);
+    let enemy_id = if asleep {
+        None
+    } else {
+        visible_enemy(boss_id, game, objects)
+    };
+    let mut took_turn = false;
+    if let Some(enemy_id) = enemy_id {
+        let abilities = &template.phases[state.phase].abilities;
+        for (ability, cooldown) in abilities.iter().zip(state.cooldowns.iter_mut()) {
+            if *cooldown == 0 && use_ability(&ability.kind, boss_id, enemy_id, game, objects) {
+                *cooldown = ability.cooldown;
+                took_turn = true;
+                break;
+            }
+        }
+    }
+    objects[boss_id].boss = Some(state);
+    took_turn
+}
+
+// False when the ability cannot do anything right now
+fn use_ability(kind: &AbilityKind, boss_id: usize, enemy_id: usize, game: &mut Game, objects: &mut [Object]) -> bool {
+    let boss_pos = objects[boss_id].pos();
+    let enemy_pos = objects[enemy_id].pos();
+    match *kind {
+        AbilityKind::PetrifyingGaze { range, num_turns } => {
+            if objects[boss_id].distance_to(&objects[enemy_id]) > range as f32
+                || !line_of_sight(boss_pos, enemy_pos, &game.map)
+            {
+                return false;
+            }
+            game.messages.add(
+                format!(
+                    "The gaze of the {} turns {} to stone!",
+                    objects[boss_id].name, objects[enemy_id].name
+                ),
+                LIGHT_BLUE,
+            );
+            objects[enemy_id].add_status(StatusKind::Paralysed, num_turns);
+            true
+        }
+        AbilityKind::Summon { ref monster, count } => {
+            let template = match game.data.monster(monster) {
+                Some(template) => template.clone(),
+                None => return false,
+            };
+            let spots: Vec<(i32, i32)> = game
+                .map
+                .neighbours(boss_pos.0, boss_pos.1)
+                .filter(|&(x, y)| {
+                    !is_blocked(x, y, &game.map, objects)
+                        && !game.spawned.iter().any(|object| object.blocks && object.pos() == (x, y))
+                })
+                .take(count as usize)
+                .collect();
+            if spots.is_empty() {
+                return false;
+            }
+            for (x, y) in spots {
+                let mut minion = template.spawn(x, y, &mut game.rng);
+                minion.faction = objects[boss_id].faction.clone();
+                if let Some(senses) = minion.senses.as_mut() {
+                    senses.awareness = Awareness::Hunting { last_known: enemy_pos };
+                }
+                game.messages.add(
+                    format!("A {} answers the call of the {}!", minion.name, objects[boss_id].name),
+                    ORANGE,
+                );
+                game.spawned.push(minion);
+            }
+            true
+        }
+    }
+}
+
+pub(crate) fn boss_death(boss: &mut Object, game: &mut Game) {
+    let name = boss.name.clone();
+    let effects = boss
+        .boss
+        .take()
+        .and_then(|state| game.data.boss(&state.name))
+        .map_or(vec![], |template| template.on_death.clone());
+    monster_death(boss, game);
+    for effect in effects {
+        match effect {
+            DeathEffect::DropLoot { items } => {
+                for item in items {
+                    game.spawned.extend(game.data.item(&item).map(|template| template.spawn(boss.x, boss.y)));
+                }
+                game.messages.add(format!("The {} leaves its treasure behind", name), LIGHT_VIOLET);
+            }
+            DeathEffect::CollapseArena { radius } => {
+                let map = &mut game.map;
+                for x in (boss.x - radius).max(1)..=(boss.x + radius).min(map.width() - 2) {
+                    for y in (boss.y - radius).max(1)..=(boss.y + radius).min(map.height() - 2) {
+                        if map.is_blocked(x, y) && boss.distance(x, y) <= radius as f32 {
+                            map.set(x, y, Tile::empty());
+                        }
+                    }
+                }
+                game.messages.add("The walls of the lair come crashing down!", ORANGE);
+            }
+        }
+    }
+}
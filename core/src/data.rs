@@ -0,0 +1,207 @@
+//data.rs
+// Game content read from the data directory at startup, designers edit
+// these files instead of the code. Never saved: a loaded game gets the
+// data of the running executable.
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+
+use crate::boss::BossTemplate;
+use crate::faction::*;
+use crate::identify::{validate_appearances, Appearances};
+use crate::object::{Item, Object};
+use crate::spawn::*;
+use crate::vault::Vault;
+
+#[derive(Clone, Debug, Default)]
+pub struct GameData {
+    pub factions: Vec<Faction>,
+    pub monsters: Vec<MonsterTemplate>,
+    pub items: Vec<ItemTemplate>,
+    pub appearances: Appearances,
+    pub bosses: Vec<BossTemplate>,
+    pub vaults: Vec<Vault>,
+    pub spawn_tables: SpawnTables,
+}
+
+impl GameData {
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<GameData, DataError> {
+        let dir = dir.as_ref();
+        let factions_path = dir.join("factions.json");
+        let factions: Vec<Faction> = load_entries(&factions_path, Faction::validate)?;
+        let faction_names: Vec<String> = factions.iter().map(|faction| faction.name.clone()).collect();
+        let known_faction = |name: &str| {
+            if faction_names.iter().any(|known| known == name) {
+                Ok(())
+            } else {
+                Err(format!("no faction called \"{}\" in factions.json", name))
+            }
+        };
+        known_faction(PLAYER_FACTION).map_err(|message| file_error(&factions_path, message))?;
+        for (index, faction) in factions.iter().enumerate() {
+            for name in &faction.hostile_to {
+                known_faction(name).map_err(|message| DataError {
+                    file: factions_path.display().to_string(),
+                    entry: Some(format!("#{} \"{}\"", index, faction.name)),
+                    message,
+                })?;
+            }
+        }
+        let monsters = load_entries(&dir.join("monsters.json"), |monster: &MonsterTemplate| {
+            monster.validate()?;
+            known_faction(&monster.faction)
+        })?;
+        let items = load_entries(&dir.join("items.json"), |item: &ItemTemplate| {
+            item.validate()?;
+            match item.effect {
+                Item::Convert { ref faction, .. } => known_faction(faction),
+                _ => Ok(()),
+            }
+        })?;
+        let appearances_path = dir.join("appearances.json");
+        let appearances: Appearances = load_file(&appearances_path, |appearances: &Appearances| {
+            validate_appearances(appearances)?;
+            // an appearance must not pass for a known item
+            let taken = items.iter().find(|item| {
+                appearances
+                    .values()
+                    .flat_map(|category| &category.appearances)
+                    .any(|entry| entry.name == item.name)
+            });
+            match taken {
+                Some(item) => Err(format!("\"{}\" is already the name of an item", item.name)),
+                None => Ok(()),
+            }
+        })?;
+        for (index, item) in items.iter().enumerate() {
+            let category = match item.unidentified {
+                Some(ref category) => category,
+                None => continue,
+            };
+            let entry_error = |message: String| DataError {
+                file: dir.join("items.json").display().to_string(),
+                entry: Some(format!("#{} \"{}\"", index, item.name)),
+                message,
+            };
+            let available = match appearances.get(category) {
+                Some(entries) => entries.appearances.len(),
+                None => return Err(entry_error(format!("no \"{}\" appearances in appearances.json", category))),
+            };
+            let needed = items.iter().filter(|other| other.unidentified.as_ref() == Some(category)).count();
+            if needed > available {
+                return Err(entry_error(format!(
+                    "{} items hide behind \"{}\" appearances, appearances.json only has {}",
+                    needed, category, available
+                )));
+            }
+        }
+        let bosses = load_entries(&dir.join("bosses.json"), |boss: &BossTemplate| {
+            boss.validate(&monsters, &items)?;
+            known_faction(&boss.faction)
+        })?;
+        let vaults = load_entries(&dir.join("vaults.json"), Vault::validate)?;
+        let spawn_tables = load_file(&dir.join("spawn_tables.json"), |tables: &SpawnTables| {
+            tables.validate(&monsters, &items)
+        })?;
+        Ok(GameData {
+            factions,
+            monsters,
+            items,
+            appearances,
+            bosses,
+            vaults,
+            spawn_tables,
+        })
+    }
+
+    // Creatures without a faction are nobody's enemies
+    pub fn are_hostile(&self, first: &Object, second: &Object) -> bool {
+        match (&first.faction, &second.faction) {
+            (Some(first), Some(second)) => are_hostile(&self.factions, first, second),
+            _ => false,
+        }
+    }
+
+    pub fn monster(&self, name: &str) -> Option<&MonsterTemplate> {
+        self.monsters.iter().find(|monster| monster.name == name)
+    }
+
+    pub fn item(&self, name: &str) -> Option<&ItemTemplate> {
+        self.items.iter().find(|item| item.name == name)
+    }
+
+    pub fn boss(&self, name: &str) -> Option<&BossTemplate> {
+        self.bosses.iter().find(|boss| boss.name == name)
+    }
+}
+
+// Points at the file, and the entry when it is a single one that is wrong
+#[derive(Debug)]
+pub struct DataError {
+    pub file: String,
+    pub entry: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.entry {
+            Some(ref entry) => write!(f, "{}, entry {}: {}", self.file, entry, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+impl Error for DataError {}
+
+fn file_error(path: &Path, message: String) -> DataError {
+    DataError {
+        file: path.display().to_string(),
+        entry: None,
+        message,
+    }
+}
+
+// A data file holding a single JSON object
+pub(crate) fn load_file<T, F>(path: &Path, validate: F) -> Result<T, DataError>
+where
+    T: DeserializeOwned,
+    F: Fn(&T) -> Result<(), String>,
+{
+    let text = fs::read_to_string(path).map_err(|e| file_error(path, e.to_string()))?;
+    let value: T = serde_json::from_str(&text).map_err(|e| file_error(path, e.to_string()))?;
+    validate(&value).map_err(|message| file_error(path, message))?;
+    Ok(value)
+}
+
+// A data file is a JSON list, each entry is read and checked on its own so
+// the error can name it
+pub(crate) fn load_entries<T, F>(path: &Path, validate: F) -> Result<Vec<T>, DataError>
+where
+    T: DeserializeOwned,
+    F: Fn(&T) -> Result<(), String>,
+{
+    let text = fs::read_to_string(path).map_err(|e| file_error(path, e.to_string()))?;
+    let values: Vec<serde_json::Value> =
+        serde_json::from_str(&text).map_err(|e| file_error(path, e.to_string()))?;
+
+    let mut entries = vec![];
+    for (index, value) in values.into_iter().enumerate() {
+        let entry_name = match value.get("name").and_then(|name| name.as_str()) {
+            Some(name) => format!("#{} \"{}\"", index, name),
+            None => format!("#{}", index),
+        };
+        let entry_error = |message: String| DataError {
+            file: path.display().to_string(),
+            entry: Some(entry_name.clone()),
+            message,
+        };
+        let entry: T = serde_json::from_value(value).map_err(|e| entry_error(e.to_string()))?;
+        validate(&entry).map_err(entry_error)?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
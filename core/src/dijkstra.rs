@@ -0,0 +1,132 @@
+//dijkstra.rs
+// Dijkstra maps: every floor tile holds its walking distance to the closest
+// goal, a monster only has to step downhill to get there. One map serves
+// every monster, much cheaper than a search each when the level is crowded.
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::map::Map;
+use crate::object::Object;
+use crate::rules::is_blocked;
+use crate::PLAYER;
+
+// What a step costs, big enough for the flee factor to stay precise
+pub const STEP_COST: i32 = 10;
+// Fleeing multiplies the distances by -1.2 before the rescan, a fleeing
+// monster would rather run past the player to a far exit than get cornered
+const FLEE_FACTOR_NUMERATOR: i32 = -12;
+const FLEE_FACTOR_DENOMINATOR: i32 = 10;
+
+#[derive(Clone, Debug, Default)]
+pub struct DijkstraMap {
+    width: i32,
+    height: i32,
+    // None where no goal can be reached
+    values: Vec<Option<i32>>,
+}
+
+impl DijkstraMap {
+    // Goals start at 0
+    pub fn new(map: &Map, goals: &[(i32, i32)]) -> Self {
+        let sources: Vec<_> = goals.iter().map(|&goal| (goal, 0)).collect();
+        DijkstraMap::from_sources(map, &sources)
+    }
+
+    // Any tile can start with any value, the scan brings down the others
+    pub fn from_sources(map: &Map, sources: &[((i32, i32), i32)]) -> Self {
+        let mut dijkstra = DijkstraMap {
+            width: map.width(),
+            height: map.height(),
+            values: vec![None; (map.width() * map.height()) as usize],
+        };
+        let mut open = BinaryHeap::new();
+        for &((x, y), value) in sources {
+            if !map.is_blocked(x, y) && dijkstra.value(x, y).is_none_or(|old| value < old) {
+                dijkstra.set(x, y, value);
+                open.push(Reverse((value, (x, y))));
+            }
+        }
+        while let Some(Reverse((value, (x, y)))) = open.pop() {
+            if dijkstra.value(x, y).is_some_and(|best| value > best) {
+                continue;
+            }
+            for (next_x, next_y) in map.neighbours(x, y) {
+                if map.is_blocked(next_x, next_y) {
+                    continue;
+                }
+                let next_value = value + STEP_COST;
+                if dijkstra.value(next_x, next_y).is_none_or(|old| next_value < old) {
+                    dijkstra.set(next_x, next_y, next_value);
+                    open.push(Reverse((next_value, (next_x, next_y))));
+                }
+            }
+        }
+        dijkstra
+    }
+
+    // Going down this one leads away from the goals, and out of dead ends
+    pub fn flee(&self, map: &Map) -> Self {
+        let sources: Vec<_> = map
+            .positions()
+            .filter_map(|(x, y)| {
+                self.value(x, y)
+                    .map(|value| ((x, y), value * FLEE_FACTOR_NUMERATOR / FLEE_FACTOR_DENOMINATOR))
+            })
+            .collect();
+        DijkstraMap::from_sources(map, &sources)
+    }
+
+    pub fn value(&self, x: i32, y: i32) -> Option<i32> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        self.values[(x + y * self.width) as usize]
+    }
+
+    // The free neighbour with the lowest value, if it is lower than here
+    pub fn downhill(&self, x: i32, y: i32, map: &Map, objects: &[Object]) -> Option<(i32, i32)> {
+        let here = self.value(x, y)?;
+        map.neighbours(x, y)
+            .filter(|&(next_x, next_y)| !is_blocked(next_x, next_y, map, objects))
+            .filter_map(|(next_x, next_y)| self.value(next_x, next_y).map(|value| (value, (next_x, next_y))))
+            .filter(|&(value, _)| value < here)
+            .min_by_key(|&(value, _)| value)
+            .map(|(_, pos)| pos)
+    }
+
+    fn set(&mut self, x: i32, y: i32, value: i32) {
+        self.values[(x + y * self.width) as usize] = Some(value);
+    }
+}
+
+// The maps the monsters share, rebuilt before they take their turn: the
+// player stays put while they move
+#[derive(Clone, Debug, Default)]
+pub struct AiMaps {
+    pub to_player: DijkstraMap,
+    pub flee_player: DijkstraMap,
+    // where fleeing monsters with nobody to regroup with run to
+    pub to_stairs: DijkstraMap,
+    // what greedy monsters wander to
+    pub to_items: DijkstraMap,
+}
+
+impl AiMaps {
+    pub fn compute(map: &Map, objects: &[Object]) -> Self {
+        let to_player = DijkstraMap::new(map, &[objects[PLAYER].pos()]);
+        let flee_player = to_player.flee(map);
+        let goals = |is_goal: fn(&Object) -> bool| -> Vec<(i32, i32)> {
+            objects
+                .iter()
+                .filter(|object| is_goal(object))
+                .map(|object| object.pos())
+                .collect()
+        };
+        AiMaps {
+            to_player,
+            flee_player,
+            to_stairs: DijkstraMap::new(map, &goals(|object| object.stairs.is_some())),
+            to_items: DijkstraMap::new(map, &goals(|object| object.item.is_some())),
+        }
+    }
+}
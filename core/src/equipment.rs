@@ -0,0 +1,114 @@
+//equipment.rs
+// Weapons and armour the player carries in the inventory. Once equipped
+// their bonuses add up with the base stats of the fighter, one piece in
+// every slot at most.
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::color::*;
+use crate::game::Game;
+use crate::object::Object;
+use crate::PLAYER;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Slot {
+    RightHand,
+    LeftHand,
+    Head,
+    Body,
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Slot::RightHand => "right hand",
+            Slot::LeftHand => "left hand",
+            Slot::Head => "head",
+            Slot::Body => "body",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Equipment {
+    pub slot: Slot,
+    // never in the data files, everything is found unequipped
+    #[serde(default)]
+    pub equipped: bool,
+    #[serde(default)]
+    pub power_bonus: i32,
+    #[serde(default)]
+    pub defense_bonus: i32,
+    #[serde(default)]
+    pub max_hp_bonus: i32,
+}
+
+impl Equipment {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.equipped {
+            return Err("equipment cannot start equipped".into());
+        }
+        if self.power_bonus < 0 || self.defense_bonus < 0 || self.max_hp_bonus < 0 {
+            return Err("bonuses cannot be negative".into());
+        }
+        Ok(())
+    }
+}
+
+// Whatever the player has equipped
+pub fn equipped(game: &Game) -> impl Iterator<Item = Equipment> + '_ {
+    game.inventory
+        .iter()
+        .filter_map(|item| item.equipment)
+        .filter(|equipment| equipment.equipped)
+}
+
+fn equipped_in_slot(slot: Slot, game: &Game) -> Option<usize> {
+    game.inventory
+        .iter()
+        .position(|item| item.equipment.is_some_and(|equipment| equipment.equipped && equipment.slot == slot))
+}
+
+// Takes off what was in the same slot first. Whether the item was put on.
+pub fn equip(inventory_id: usize, game: &mut Game, objects: &mut [Object]) -> bool {
+    let slot = match game.inventory[inventory_id].equipment {
+        Some(equipment) if !equipment.equipped => equipment.slot,
+        Some(_) => return false,
+        None => {
+            let name = game.identification.name_of(&game.inventory[inventory_id]);
+            game.messages.add(format!("The {} cannot be equipped", name), WHITE);
+            return false;
+        }
+    };
+    if let Some(old_id) = equipped_in_slot(slot, game) {
+        unequip(old_id, game, objects);
+    }
+    let item = &mut game.inventory[inventory_id];
+    if let Some(equipment) = item.equipment.as_mut() {
+        equipment.equipped = true;
+    }
+    game.messages.add(format!("Equipped {} on {}", item.name, slot), LIGHT_GREEN);
+    true
+}
+
+// Whether the item was taken off
+pub fn unequip(inventory_id: usize, game: &mut Game, objects: &mut [Object]) -> bool {
+    let item = &mut game.inventory[inventory_id];
+    let slot = match item.equipment.as_mut() {
+        Some(equipment) if equipment.equipped => {
+            equipment.equipped = false;
+            equipment.slot
+        }
+        _ => return false,
+    };
+    game.messages.add(format!("Unequipped {} from {}", item.name, slot), LIGHT_YELLOW);
+    // no more hit points than the armour left leaves room for
+    let max_hp = objects[PLAYER].max_hp(game);
+    if let Some(fighter) = objects[PLAYER].fighter.as_mut() {
+        fighter.hp = fighter.hp.min(max_hp);
+    }
+    true
+}
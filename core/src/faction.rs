@@ -0,0 +1,47 @@
+//faction.rs
+// Who fights who. Creatures attack anyone from a faction they are hostile
+// to, and one side listing the other in data/factions.json is enough.
+use serde::{Deserialize, Serialize};
+
+use crate::object::Object;
+
+// The faction of the player, factions.json must have it
+pub const PLAYER_FACTION: &str = "player";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Faction {
+    pub name: String,
+    pub hostile_to: Vec<String>,
+}
+
+impl Faction {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("a faction needs a name".into());
+        }
+        Ok(())
+    }
+}
+
+pub fn are_hostile(factions: &[Faction], first: &str, second: &str) -> bool {
+    let lists = |faction: &str, other: &str| {
+        factions
+            .iter()
+            .any(|entry| entry.name == faction && entry.hostile_to.iter().any(|name| name == other))
+    };
+    lists(first, second) || lists(second, first)
+}
+
+// A monster on the player's side, that follows them around. Looks at the
+// senses, the ai is taken out of the monster while it takes its turn.
+pub fn is_companion(object: &Object) -> bool {
+    object.senses.is_some() && object.faction.as_deref() == Some(PLAYER_FACTION)
+}
+
+// A faction forced on a creature by magic, until it wears off
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Conversion {
+    pub previous_faction: Option<String>,
+    pub num_turns: i32,
+}
@@ -0,0 +1,99 @@
+//fov.rs
+// Field of view computed straight from the map, so the rules never need
+// a libtcod window. Same idea as libtcod FOV_BASIC: rays are cast from the
+// origin to every cell on the border of the radius square.
+use std::cmp;
+
+use crate::map::{Line, Map};
+
+#[derive(Clone, Debug, Default)]
+pub struct Fov {
+    width: i32,
+    height: i32,
+    visible: Vec<bool>,
+}
+
+impl Fov {
+    pub fn new(width: i32, height: i32) -> Self {
+        Fov {
+            width,
+            height,
+            visible: vec![false; (width * height) as usize],
+        }
+    }
+
+    pub fn is_in_fov(&self, x: i32, y: i32) -> bool {
+        self.in_bounds(x, y) && self.visible[(x + y * self.width) as usize]
+    }
+
+    pub fn compute(&mut self, map: &Map, x: i32, y: i32, radius: i32, light_walls: bool) {
+        let (width, height) = (map.width(), map.height());
+        *self = Fov::new(width, height);
+        if !self.in_bounds(x, y) {
+            return;
+        }
+        // radius 0 means unlimited, like libtcod
+        let radius = if radius > 0 { radius } else { cmp::max(width, height) };
+        self.set_visible(x, y);
+
+        let (x_min, x_max) = (x - radius, x + radius);
+        let (y_min, y_max) = (y - radius, y + radius);
+        for target_x in x_min..=x_max {
+            self.cast_ray(map, (x, y), (target_x, y_min), radius, light_walls);
+            self.cast_ray(map, (x, y), (target_x, y_max), radius, light_walls);
+        }
+        for target_y in (y_min + 1)..y_max {
+            self.cast_ray(map, (x, y), (x_min, target_y), radius, light_walls);
+            self.cast_ray(map, (x, y), (x_max, target_y), radius, light_walls);
+        }
+        if light_walls {
+            self.light_walls(map, (x, y), radius);
+        }
+    }
+
+    fn cast_ray(&mut self, map: &Map, origin: (i32, i32), target: (i32, i32), radius: i32, light_walls: bool) {
+        for (x, y) in Line::new(origin, target).skip(1) {
+            if !self.in_bounds(x, y) || !in_radius(origin, (x, y), radius) {
+                return;
+            }
+            if !map.blocks_sight(x, y) {
+                self.set_visible(x, y);
+            } else {
+                if light_walls {
+                    self.set_visible(x, y);
+                }
+                return;
+            }
+        }
+    }
+
+    // Rays can slip past wall corners, light the walls touching a visible floor
+    fn light_walls(&mut self, map: &Map, origin: (i32, i32), radius: i32) {
+        let mut walls = vec![];
+        for (x, y) in map.positions() {
+            if !self.is_in_fov(x, y) || map.blocks_sight(x, y) {
+                continue;
+            }
+            walls.extend(map.neighbours(x, y).filter(|&(wall_x, wall_y)| {
+                map.blocks_sight(wall_x, wall_y) && in_radius(origin, (wall_x, wall_y), radius)
+            }));
+        }
+        for (x, y) in walls {
+            self.set_visible(x, y);
+        }
+    }
+
+    fn set_visible(&mut self, x: i32, y: i32) {
+        self.visible[(x + y * self.width) as usize] = true;
+    }
+
+    fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height
+    }
+}
+
+fn in_radius(origin: (i32, i32), pos: (i32, i32), radius: i32) -> bool {
+    let dx = pos.0 - origin.0;
+    let dy = pos.1 - origin.1;
+    dx * dx + dy * dy <= radius * radius
+}
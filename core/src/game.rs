@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use crate::area::Cloud;
+use crate::color::*;
+use crate::data::GameData;
+use crate::dijkstra::AiMaps;
+use crate::fov::Fov;
+use crate::identify::Identification;
+use crate::map::Map;
+use crate::object::Object;
+use crate::rng::GameRng;
+use serde::{Deserialize, Serialize};
+//game.rs
+
+#[derive(Serialize, Deserialize)]
+pub struct Game {
+    pub map: Map,
+    pub messages: Messages,
+    pub inventory: Vec<Object>,
+    pub dungeon_level: u32,
+    // every level the player left, as they left it
+    pub levels: BTreeMap<u32, StoredLevel>,
+    // gas lingering on the current level
+    pub clouds: Vec<Cloud>,
+    // what the items of this game look like, and which ones the player knows
+    pub identification: Identification,
+    pub seed: u64,
+    pub rng: GameRng,
+    // goes up with every action of the player or a monster, dates the noises
+    pub clock: u64,
+    // recomputed from the player position, never saved
+    #[serde(skip)]
+    pub fov: Fov,
+    // rebuilt every turn before the monsters move, never saved either
+    #[serde(skip)]
+    pub ai_maps: AiMaps,
+    // fights some monster has not had the chance to hear yet
+    #[serde(skip)]
+    pub noises: Vec<Noise>,
+    // summoned monsters and dropped loot, added to the objects when the turn ends
+    #[serde(skip)]
+    pub spawned: Vec<Object>,
+    // content files of the running game, put back by load_game
+    #[serde(skip)]
+    pub data: GameData,
+}
+
+impl Game {
+    pub fn make_noise(&mut self, pos: (i32, i32)) {
+        self.noises.push(Noise {
+            pos,
+            made_at: self.clock,
+        });
+    }
+}
+
+// Where a fight broke out, and when
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Noise {
+    pub pos: (i32, i32),
+    pub made_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StoredLevel {
+    pub map: Map,
+    // everything but the player, who travels with the game
+    pub objects: Vec<Object>,
+    pub clouds: Vec<Cloud>,
+}
+
+//messages.rs
+//
+#[derive(Default, Serialize, Deserialize)]
+pub struct Messages {
+    pub messages: Vec<(String, Color)>,
+
+}
+
+impl Messages {
+   pub fn new() -> Self {
+       Self { messages: vec![] }
+   }
+
+   pub fn add<T: Into<String>>(&mut self, message: T, color: Color) {
+       self.messages.push((message.into(), color));
+   }
+
+   pub fn iter(&self) -> impl DoubleEndedIterator<Item = &(String, Color)> {
+       self.messages.iter()
+   }
+}
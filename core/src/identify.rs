@@ -0,0 +1,113 @@
+//identify.rs
+// Scrolls, potions and wands do not tell what they are. Every game deals
+// the appearances of data/appearances.json out anew among the item kinds
+// that have one, and the player only learns the true name of a kind by
+// using one or reading a scroll of identify on it. The look goes with the
+// appearance as well: every kind of a category shares its glyph, the colour
+// is the appearance's, and both stay once the kind is known. What the player
+// knows is saved with the game.
+use std::collections::{BTreeMap, BTreeSet};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::color::Color;
+use crate::data::GameData;
+use crate::object::Object;
+use crate::rng::GameRng;
+
+// One category of appearances.json, "potion" gives the glyph of all the
+// potions and ["murky potion", ...]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Category {
+    pub glyph: char,
+    pub appearances: Vec<AppearanceEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppearanceEntry {
+    pub name: String,
+    pub color: Color,
+}
+
+pub type Appearances = BTreeMap<String, Category>;
+
+pub fn validate_appearances(appearances: &Appearances) -> Result<(), String> {
+    let mut seen = BTreeSet::new();
+    for (category, entries) in appearances {
+        for entry in &entries.appearances {
+            if !seen.insert(&entry.name) {
+                return Err(format!("{}: \"{}\" shows up more than once", category, entry.name));
+            }
+        }
+    }
+    Ok(())
+}
+
+// What an unidentified kind looks like
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Appearance {
+    pub name: String,
+    pub glyph: char,
+    pub color: Color,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Identification {
+    // by item name
+    pub appearances: BTreeMap<String, Appearance>,
+    pub known: BTreeSet<String>,
+}
+
+impl Identification {
+    pub fn new(data: &GameData, rng: &mut GameRng) -> Self {
+        let mut appearances = BTreeMap::new();
+        for (category, entries) in &data.appearances {
+            let mut entries = entries.appearances.clone();
+            rng.shuffle(&mut entries);
+            let kinds = data
+                .items
+                .iter()
+                .filter(|item| item.unidentified.as_ref() == Some(category));
+            for (item, entry) in kinds.zip(entries) {
+                let appearance = Appearance {
+                    name: entry.name,
+                    glyph: data.appearances[category].glyph,
+                    color: entry.color,
+                };
+                appearances.insert(item.name.clone(), appearance);
+            }
+        }
+        Identification {
+            appearances,
+            known: BTreeSet::new(),
+        }
+    }
+
+    pub fn is_known(&self, name: &str) -> bool {
+        self.known.contains(name) || !self.appearances.contains_key(name)
+    }
+
+    // The name the player knows the item by
+    pub fn name_of(&self, item: &Object) -> String {
+        match self.appearances.get(&item.name) {
+            Some(appearance) if !self.known.contains(&item.name) => appearance.name.clone(),
+            _ => item.name.clone(),
+        }
+    }
+
+    // The glyph and colour to draw the object with
+    pub fn look_of(&self, object: &Object) -> (char, Color) {
+        match self.appearances.get(&object.name) {
+            Some(appearance) => (appearance.glyph, appearance.color),
+            None => (object.char, object.color),
+        }
+    }
+
+    // Whether the kind was a mystery until now
+    pub fn identify(&mut self, name: &str) -> bool {
+        !self.is_known(name) && self.known.insert(name.into())
+    }
+}
@@ -0,0 +1,410 @@
+//items.rs
+use rand::Rng;
+
+use crate::area::{area, explode, release, splash_heal, Cloud};
+use crate::color::*;
+use crate::faction::Conversion;
+use crate::game::*;
+use crate::equipment::{equip, unequip};
+use crate::object::*;
+use crate::rules::line_of_sight;
+use crate::status::StatusKind;
+use crate::*;
+
+pub fn pick_item_up(object_id: usize, game: &mut Game, objects: &mut Vec<Object>) {
+    if game.inventory.len() >= MAX_INVENTORY {
+        game.messages.add("Your inventory is full", RED);
+    } else {
+        let item = objects.swap_remove(object_id);
+        game.messages.add(format!("You picked {}", game.identification.name_of(&item)), GREEN);
+        game.inventory.push(item);
+    }
+}
+
+// `target` is the tile picked by the player, for the items that have a range.
+// Whether the item was used.
+pub fn use_item(inventory_id: usize, target: Option<(i32, i32)>, game: &mut Game, objects: &mut [Object]) -> bool {
+    let shown_name = game.identification.name_of(&game.inventory[inventory_id]);
+    if game.inventory[inventory_id].charges.is_some_and(|charges| charges.current == 0) {
+        game.messages.add(format!("The {} has no charges left", shown_name), ORANGE);
+        return false;
+    }
+    if let Some(item) = game.inventory[inventory_id].item.clone() {
+        let name = game.inventory[inventory_id].name.clone();
+        let result = match item {
+            Item::Heal { amount } => {
+                let known = game.identification.is_known(&name);
+                cast_heal(amount, known, game, objects)
+            }
+            Item::Lightning { damage, range } => cast_lightning(damage, range, target, game, objects),
+            Item::Fireball { damage, radius, range } => cast_fireball(damage, radius, range, target, game, objects),
+            Item::Confuse { range, num_turns } => cast_confuse(range, num_turns, target, game, objects),
+            Item::Convert {
+                faction,
+                range,
+                num_turns,
+            } => cast_convert(&faction, range, num_turns, target, game, objects),
+            Item::Potion { status, num_turns } => drink(status, num_turns, game, objects),
+            Item::Equipment => toggle_equipment(inventory_id, game, objects),
+            Item::Recharge { .. } | Item::Identify => {
+                game.messages.add(format!("Choose what to read the {} on", shown_name), WHITE);
+                UseResult::Cancelled
+            }
+        };
+        match result {
+            // a wand only loses a charge
+            UseResult::UsedUp => {
+                let item = &mut game.inventory[inventory_id];
+                match item.charges.as_mut() {
+                    Some(charges) => {
+                        charges.current -= 1;
+                        if charges.current == 0 {
+                            game.messages.add(format!("The {} is out of charges", shown_name), LIGHT_GREY);
+                        }
+                    }
+                    None => {
+                        game.inventory.remove(inventory_id);
+                    }
+                }
+                learn(&shown_name, &name, game);
+                true
+            }
+            UseResult::Kept => {
+                learn(&shown_name, &name, game);
+                true
+            }
+            UseResult::Cancelled => {
+                game.messages.add("Cancelled", WHITE);
+                false
+            }
+        }
+    } else {
+        game.messages.add(format!("The {} cannot be used", shown_name), WHITE);
+        false
+    }
+}
+
+// Using an item gives its kind away
+fn learn(shown_name: &str, name: &str, game: &mut Game) {
+    if game.identification.identify(name) {
+        game.messages.add(format!("The {} was a {}", shown_name, name), LIGHT_CYAN);
+    }
+}
+
+// Only potions are worth throwing, they shatter into a cloud of whatever
+// they would have done to the one drinking them, healing ones splash whoever
+// is around. Whether it was thrown.
+pub fn throw_item(inventory_id: usize, target: Option<(i32, i32)>, game: &mut Game, objects: &mut [Object]) -> bool {
+    let item = &game.inventory[inventory_id];
+    let shown_name = game.identification.name_of(item);
+    let effect = match item.item {
+        Some(ref effect @ Item::Potion { .. }) => effect.clone(),
+        // staves of healing have charges
+        Some(ref effect @ Item::Heal { .. }) if item.charges.is_none() => effect.clone(),
+        _ => {
+            game.messages.add(format!("The {} cannot be thrown", shown_name), WHITE);
+            return false;
+        }
+    };
+    let target = match target {
+        Some(target) if in_reach(target, THROW_RANGE, game, objects) => target,
+        Some(_) => {
+            game.messages.add("That is out of reach", ORANGE);
+            game.messages.add("Cancelled", WHITE);
+            return false;
+        }
+        None => {
+            game.messages.add("Cancelled", WHITE);
+            return false;
+        }
+    };
+    let item = game.inventory.remove(inventory_id);
+    game.messages.add(format!("The {} shatters", shown_name), LIGHT_VIOLET);
+    learn(&shown_name, &item.name, game);
+    match effect {
+        Item::Heal { amount } => splash_heal(target, THROWN_POTION_RADIUS, amount, game, objects),
+        Item::Potion { status, num_turns } => {
+            // a potion of poison gives a cloud of poison
+            let name = match item.name.strip_prefix("potion of ") {
+                Some(contents) => format!("cloud of {}", contents),
+                None => format!("cloud of {}", item.name),
+            };
+            let (_, color) = game.identification.look_of(&item);
+            let cloud = Cloud {
+                name,
+                color,
+                tiles: area(target, THROWN_POTION_RADIUS, &game.map),
+                status,
+                status_turns: num_turns,
+                num_turns: CLOUD_TURNS,
+            };
+            release(cloud, game, objects);
+        }
+        _ => {}
+    }
+    true
+}
+
+// Reads the scroll at `scroll_id` on another item of the inventory, whether
+// it was used
+pub fn use_on(scroll_id: usize, target_id: usize, game: &mut Game, objects: &mut [Object]) -> bool {
+    let shown_name = game.identification.name_of(&game.inventory[scroll_id]);
+    if scroll_id == target_id {
+        game.messages.add(format!("The {} cannot be read on itself", shown_name), WHITE);
+        return false;
+    }
+    let name = game.inventory[scroll_id].name.clone();
+    let used = match game.inventory[scroll_id].item {
+        Some(Item::Recharge { amount }) => recharge(amount, scroll_id, target_id, game, objects),
+        Some(Item::Identify) => identify(scroll_id, target_id, game),
+        _ => {
+            game.messages.add(format!("The {} cannot be used on another item", shown_name), WHITE);
+            false
+        }
+    };
+    if used {
+        learn(&shown_name, &name, game);
+    }
+    used
+}
+
+// Too many charges may make the wand blow up in the player's hands. Both
+// the scroll and the wand are gone then, the scroll only otherwise.
+fn recharge(amount: i32, scroll_id: usize, wand_id: usize, game: &mut Game, objects: &mut [Object]) -> bool {
+    let wand_name = game.identification.name_of(&game.inventory[wand_id]);
+    let charges = match game.inventory[wand_id].charges.as_mut() {
+        Some(charges) => charges,
+        None => {
+            game.messages.add(format!("The {} cannot be recharged", wand_name), WHITE);
+            return false;
+        }
+    };
+    let overcharged = charges.current + amount > charges.max;
+    charges.current = (charges.current + amount).min(charges.max);
+    game.inventory.remove(scroll_id);
+    if overcharged && game.rng.gen_range(0, 100) < OVERCHARGE_EXPLODE_CHANCE {
+        let wand_id = if scroll_id < wand_id { wand_id - 1 } else { wand_id };
+        game.inventory.remove(wand_id);
+        game.messages.add(format!("The {} overloads and explodes!", wand_name), RED);
+        explode(objects[PLAYER].pos(), WAND_EXPLOSION_RADIUS, WAND_EXPLOSION_DAMAGE, game, objects);
+    } else {
+        game.messages.add(format!("The {} glows with new power", wand_name), LIGHT_BLUE);
+    }
+    true
+}
+
+// The scroll is kept when there was nothing left to learn
+fn identify(scroll_id: usize, target_id: usize, game: &mut Game) -> bool {
+    let item = &game.inventory[target_id];
+    let shown_name = game.identification.name_of(item);
+    if game.identification.is_known(&item.name) {
+        game.messages.add(format!("You already know what the {} is", shown_name), WHITE);
+        return false;
+    }
+    let name = item.name.clone();
+    game.identification.identify(&name);
+    game.messages.add(format!("The {} is a {}", shown_name, name), LIGHT_CYAN);
+    game.inventory.remove(scroll_id);
+    true
+}
+
+pub fn drop_item(inventory_id: usize, game: &mut Game, objects: &mut Vec<Object>) {
+     unequip(inventory_id, game, objects);
+     let mut item = game.inventory.remove(inventory_id);
+     item.set_pos(objects[PLAYER].x, objects[PLAYER].y);
+     game.messages.add(format!("You dropped a {}", game.identification.name_of(&item)), YELLOW);
+     objects.push(item);
+}
+
+// Only a player who knows what the item does can save it for later
+fn cast_heal(
+    amount: i32,
+    known: bool,
+    game: &mut Game,
+    objects: &mut [Object],
+    ) -> UseResult {
+    if let Some(fighter) = objects[PLAYER].fighter {
+        if known && fighter.hp == objects[PLAYER].max_hp(game) {
+            game.messages.add("You are already at full health", ORANGE);
+            return UseResult::Cancelled;
+        }
+        game.messages.add("You wounds starts to feel better!", LIGHT_VIOLET);
+        objects[PLAYER].heal(amount, game);
+        return UseResult::UsedUp;
+    }
+    UseResult::Cancelled
+}
+
+// Equipment stays in the inventory, worn or not
+fn toggle_equipment(inventory_id: usize, game: &mut Game, objects: &mut [Object]) -> UseResult {
+    let toggled = if game.inventory[inventory_id].equipment.is_some_and(|equipment| equipment.equipped) {
+        unequip(inventory_id, game, objects)
+    } else {
+        equip(inventory_id, game, objects)
+    };
+    if toggled {
+        UseResult::Kept
+    } else {
+        UseResult::Cancelled
+    }
+}
+
+fn drink(status: StatusKind, num_turns: i32, game: &mut Game, objects: &mut [Object]) -> UseResult {
+    game.messages.add(format!("You feel {}", status.name()), LIGHT_VIOLET);
+    objects[PLAYER].add_status(status, num_turns);
+    UseResult::UsedUp
+}
+
+fn cast_lightning(
+    damage: i32,
+    range: i32,
+    target: Option<(i32, i32)>,
+    game: &mut Game,
+    objects: &mut [Object],
+) -> UseResult {
+    let monster_id = target_monster(target, range, game, objects);
+    if let Some(monster_id) = monster_id {
+        game.messages.add(
+            format!(
+                "A lightning bolt strike {} with a loud thunder! the damage is {} hit points",
+                objects[monster_id].name, damage
+            ),
+             LIGHT_BLUE
+        );
+        objects[monster_id].take_damage(damage, game);
+        UseResult::UsedUp
+    } else {
+        UseResult::Cancelled
+    }
+}
+
+// Anywhere in reach, an empty tile will do
+fn cast_fireball(
+    damage: i32,
+    radius: i32,
+    range: i32,
+    target: Option<(i32, i32)>,
+    game: &mut Game,
+    objects: &mut [Object],
+) -> UseResult {
+    match target {
+        Some(target) if in_reach(target, range, game, objects) => {
+            game.messages.add("The fireball explodes!", ORANGE);
+            explode(target, radius, damage, game, objects);
+            UseResult::UsedUp
+        }
+        Some(_) => {
+            game.messages.add("That is out of reach", ORANGE);
+            UseResult::Cancelled
+        }
+        None => UseResult::Cancelled,
+    }
+}
+
+fn cast_confuse(
+    range: i32,
+    num_turns: i32,
+    target: Option<(i32, i32)>,
+    game: &mut Game,
+    objects: &mut [Object],
+    ) -> UseResult {
+    let monster_id = target_monster(target, range, game, objects);
+    if let Some(monster_id) = monster_id {
+        objects[monster_id].add_status(StatusKind::Confused, num_turns);
+        game.messages.add(
+            format!(
+                "{} seems confused, as he starts to stumble around",
+                objects[monster_id].name
+                ),
+                LIGHT_GREEN,
+            );
+        UseResult::UsedUp
+    } else {
+        UseResult::Cancelled
+    }
+}
+
+fn cast_convert(
+    faction: &str,
+    range: i32,
+    num_turns: Option<i32>,
+    target: Option<(i32, i32)>,
+    game: &mut Game,
+    objects: &mut [Object],
+    ) -> UseResult {
+    let monster_id = target_monster(target, range, game, objects);
+    if let Some(monster_id) = monster_id.filter(|&id| objects[id].boss.is_some()) {
+        game.messages.add(
+            format!("The {} shrugs off the spell", objects[monster_id].name),
+            ORANGE,
+        );
+        UseResult::UsedUp
+    } else if let Some(monster_id) = monster_id {
+        let monster = &mut objects[monster_id];
+        // a second spell keeps the faction it had before any magic
+        let previous_faction = match monster.conversion.take() {
+            Some(conversion) => conversion.previous_faction,
+            None => monster.faction.clone(),
+        };
+        monster.conversion = num_turns.map(|num_turns| Conversion {
+            previous_faction,
+            num_turns,
+        });
+        monster.faction = Some(faction.into());
+        monster.path = None;
+        if let Some(senses) = monster.senses.as_mut() {
+            senses.awareness = Awareness::Wandering;
+        }
+        game.messages.add(
+            format!("The {} now sides with the {}!", monster.name, faction),
+            LIGHT_GREEN,
+        );
+        UseResult::UsedUp
+    } else {
+        UseResult::Cancelled
+    }
+}
+
+// The player sees the tile, it is close enough and nothing stands in the way
+pub fn in_reach(target: (i32, i32), range: i32, game: &Game, objects: &[Object]) -> bool {
+    objects[PLAYER].distance(target.0, target.1) <= range as f32
+        && game.fov.is_in_fov(target.0, target.1)
+        && line_of_sight(objects[PLAYER].pos(), target, &game.map)
+}
+
+// The monster on the tile the player picked, no tile means they gave up
+fn target_monster(target: Option<(i32, i32)>, range: i32, game: &mut Game, objects: &[Object]) -> Option<usize> {
+    let target = target?;
+    if !in_reach(target, range, game, objects) {
+        game.messages.add("That is out of reach", ORANGE);
+        return None;
+    }
+    let monster_id = objects
+        .iter()
+        .position(|object| object.pos() == target && object.fighter.is_some() && object.ai.is_some());
+    if monster_id.is_none() {
+        game.messages.add("There is nobody there", ORANGE);
+    }
+    monster_id
+}
+
+// Where the targeting cursor starts
+pub fn closest_monster(game: &Game, objects: &[Object], max_range: i32) -> Option<usize> {
+    let mut closest_enemy = None;
+    let mut closest_dist = (max_range + 1) as f32;
+    for (id, object) in objects.iter().enumerate() {
+        if (id != PLAYER)
+            && object.fighter.is_some()
+            && object.ai.is_some()
+            && game.data.are_hostile(&objects[PLAYER], object)
+            && game.fov.is_in_fov(object.x, object.y)
+        {
+            let dist = objects[PLAYER].distance_to(object);
+            if dist < closest_dist {
+                closest_enemy = Some(id);
+                closest_dist = dist
+            }
+        }
+    }
+    closest_enemy
+}
@@ -0,0 +1,58 @@
+// roguelite-core: the rules of the Maze of the Blue Medusa.
+// Nothing in here knows about libtcod, front ends feed player actions in
+// and draw `Game` and the object list however they like.
+pub mod ai;
+pub mod area;
+pub mod boss;
+pub mod color;
+pub mod data;
+pub mod dijkstra;
+pub mod equipment;
+pub mod faction;
+pub mod fov;
+pub mod game;
+pub mod identify;
+pub mod items;
+pub mod map;
+pub mod mapgen;
+pub mod object;
+pub mod pathfinding;
+pub mod replay;
+pub mod rng;
+pub mod rules;
+pub mod save;
+pub mod schedule;
+pub mod spawn;
+pub mod status;
+pub mod vault;
+
+// ===================== CONST
+pub const MAP_WIDTH: i32 = 80;
+pub const MAP_HEIGHT: i32 = 43;
+
+pub const FOV_LIGHT_WALLS: bool = true;
+pub const TORCH_RADIUS: i32 = 10;
+// how far a blinded player still sees
+pub const BLINDED_RADIUS: i32 = 1;
+
+pub const ROOM_MAX_SIZE: i32 = 10;
+pub const ROOM_MIN_SIZE: i32 = 6;
+pub const MAX_ROOMS: i32 = 30;
+
+
+pub const PLAYER: usize = 0;
+pub const PLAYER_BASE_MAX_HP: i32 = 30;
+pub const PLAYER_BASE_DEFENSE: i32 = 2;
+pub const PLAYER_BASE_POWER: i32 = 5;
+
+pub const MAX_INVENTORY: usize = 26;
+// how far potions fly, the cloud they shatter into and how long it lingers
+pub const THROW_RANGE: i32 = 6;
+pub const THROWN_POTION_RADIUS: i32 = 1;
+pub const CLOUD_TURNS: i32 = 5;
+// companions this close take the stairs with the player
+pub const FOLLOW_STAIRS_DISTANCE: f32 = 3.0;
+// percent chance a wand blows up when recharged past its maximum, and how hard
+pub const OVERCHARGE_EXPLODE_CHANCE: u32 = 50;
+pub const WAND_EXPLOSION_RADIUS: i32 = 1;
+pub const WAND_EXPLOSION_DAMAGE: i32 = 8;
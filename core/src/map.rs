@@ -0,0 +1,215 @@
+//map.rs
+use serde::{Deserialize, Serialize};
+
+// ===================== MAP
+// Tiles are stored row after row, every access is bounds checked and
+// anything outside the map counts as a wall.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Map {
+    width: i32,
+    height: i32,
+    tiles: Vec<Tile>,
+}
+
+const NEIGHBOURS: [(i32, i32); 8] = [
+    (-1, -1), (0, -1), (1, -1),
+    (-1, 0),           (1, 0),
+    (-1, 1),  (0, 1),  (1, 1),
+];
+
+const CARDINAL_NEIGHBOURS: [(i32, i32); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+impl Map {
+    pub fn new(width: i32, height: i32, tile: Tile) -> Self {
+        Map {
+            width,
+            height,
+            tiles: vec![tile; (width * height) as usize],
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<&Tile> {
+        if self.in_bounds(x, y) {
+            self.tiles.get((x + y * self.width) as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: i32, y: i32) -> Option<&mut Tile> {
+        if self.in_bounds(x, y) {
+            self.tiles.get_mut((x + y * self.width) as usize)
+        } else {
+            None
+        }
+    }
+
+    // Out of bounds writes are ignored, generators can draw past the edges
+    pub fn set(&mut self, x: i32, y: i32, tile: Tile) {
+        if let Some(old_tile) = self.get_mut(x, y) {
+            *old_tile = tile;
+        }
+    }
+
+    pub fn is_blocked(&self, x: i32, y: i32) -> bool {
+        self.get(x, y).is_none_or(|tile| tile.blocked)
+    }
+
+    pub fn blocks_sight(&self, x: i32, y: i32) -> bool {
+        self.get(x, y).is_none_or(|tile| tile.block_sight)
+    }
+
+    // Every position of the map, column by column
+    pub fn positions(&self) -> impl Iterator<Item = (i32, i32)> {
+        let height = self.height;
+        (0..self.width).flat_map(move |x| (0..height).map(move |y| (x, y)))
+    }
+
+    // The 8 surrounding positions that are inside the map
+    pub fn neighbours(&self, x: i32, y: i32) -> impl Iterator<Item = (i32, i32)> + '_ {
+        NEIGHBOURS
+            .iter()
+            .map(move |&(dx, dy)| (x + dx, y + dy))
+            .filter(move |&(x, y)| self.in_bounds(x, y))
+    }
+
+    // Same as `neighbours` without the diagonals
+    pub fn cardinal_neighbours(&self, x: i32, y: i32) -> impl Iterator<Item = (i32, i32)> + '_ {
+        CARDINAL_NEIGHBOURS
+            .iter()
+            .map(move |&(dx, dy)| (x + dx, y + dy))
+            .filter(move |&(x, y)| self.in_bounds(x, y))
+    }
+}
+
+// ===================== RECT
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+   pub x1: i32,
+   pub y1: i32,
+   pub x2: i32,
+   pub y2: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        Rect {
+            x1: x,
+            y1: y,
+            x2: x + w,
+            y2: y + h,
+        }
+    }
+
+    pub fn center(&self) -> (i32, i32) {
+        let center_x = (self.x1 + self.x2) / 2;
+        let center_y = (self.y1 + self.y2) / 2;
+        (center_x, center_y)
+    }
+
+    pub fn intersects_with(&self, other: &Rect) -> bool {
+        (self.x1 <= other.x2)
+            && (self.x2 >= other.x1)
+            && (self.y1 <= other.y2)
+            && (self.y2 >= other.y1)
+    }
+
+    // Every position of the rectangle, borders included
+    pub fn points(&self) -> impl Iterator<Item = (i32, i32)> {
+        let (y1, y2) = (self.y1, self.y2);
+        (self.x1..=self.x2).flat_map(move |x| (y1..=y2).map(move |y| (x, y)))
+    }
+
+    // The floor of a room dug in this rectangle, the borders stay walls
+    pub fn inner_points(&self) -> impl Iterator<Item = (i32, i32)> {
+        let (y1, y2) = (self.y1, self.y2);
+        ((self.x1 + 1)..self.x2).flat_map(move |x| ((y1 + 1)..y2).map(move |y| (x, y)))
+    }
+}
+
+// ===================== LINE
+// Bresenham line from `from` to `to`, both ends included
+pub struct Line {
+    x: i32,
+    y: i32,
+    to: (i32, i32),
+    dx: i32,
+    dy: i32,
+    step_x: i32,
+    step_y: i32,
+    error: i32,
+    done: bool,
+}
+
+impl Line {
+    pub fn new(from: (i32, i32), to: (i32, i32)) -> Self {
+        let dx = (to.0 - from.0).abs();
+        let dy = -(to.1 - from.1).abs();
+        Line {
+            x: from.0,
+            y: from.1,
+            to,
+            dx,
+            dy,
+            step_x: if from.0 < to.0 { 1 } else { -1 },
+            step_y: if from.1 < to.1 { 1 } else { -1 },
+            error: dx + dy,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for Line {
+    type Item = (i32, i32);
+
+    fn next(&mut self) -> Option<(i32, i32)> {
+        if self.done {
+            return None;
+        }
+        let point = (self.x, self.y);
+        if point == self.to {
+            self.done = true;
+        } else {
+            let double_error = 2 * self.error;
+            if double_error >= self.dy {
+                self.error += self.dy;
+                self.x += self.step_x;
+            }
+            if double_error <= self.dx {
+                self.error += self.dx;
+                self.y += self.step_y;
+            }
+        }
+        Some(point)
+    }
+}
+
+// ===================== TILE
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Tile {
+    pub blocked: bool,
+    pub block_sight: bool,
+    pub explored: bool,
+}
+
+impl Tile {
+    pub fn empty() -> Self {
+        Tile { blocked: false, block_sight: false, explored: false,}
+    }
+
+    pub fn wall() -> Self {
+        Tile { blocked: true, block_sight: true, explored: false,}
+    }
+}
@@ -0,0 +1,330 @@
+//mapgen.rs
+// Every dungeon level is dug by a `MapGenerator`, `make_map` then fills it
+// with the player, monsters, items and the stairs.
+use std::collections::VecDeque;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::color::*;
+use crate::data::GameData;
+use crate::map::*;
+use crate::object::*;
+use crate::rng::GameRng;
+use crate::rules::{free_spot_near, is_blocked};
+use crate::vault::stamp_vaults;
+use crate::*;
+
+mod bsp;
+mod caves;
+mod drunkard;
+mod rooms;
+
+pub use bsp::Bsp;
+pub use caves::Caves;
+pub use drunkard::DrunkardsWalk;
+pub use rooms::RoomsAndCorridors;
+
+// Size of the chunks cave like levels are cut into to spread objects around
+const SPAWN_AREA_SIZE: i32 = 15;
+// Orphaned floor pockets smaller than this are filled instead of connected
+const MIN_REGION_SIZE: usize = 4;
+const MAX_GENERATION_ATTEMPTS: u32 = 50;
+
+pub trait MapGenerator {
+    // None when the generator could not dig anything usable, make_map tries again
+    fn generate(&self, rng: &mut GameRng) -> Option<Level>;
+}
+
+// A freshly dug level, before anything is placed on it
+pub struct Level {
+    pub map: Map,
+    // floor tiles grouped by room (or chunk of cave), each one gets its own monsters and items
+    pub spawn_areas: Vec<Vec<(i32, i32)>>,
+    pub start: (i32, i32),
+    pub stairs: (i32, i32),
+    // guaranteed spawns, from the markers of prefab vaults
+    pub monster_spawns: Vec<(i32, i32)>,
+    pub item_spawns: Vec<(i32, i32)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GeneratorKind {
+    RoomsAndCorridors,
+    Bsp,
+    Caves,
+    DrunkardsWalk,
+}
+
+impl GeneratorKind {
+    pub fn for_level(dungeon_level: u32) -> Self {
+        use GeneratorKind::*;
+        match dungeon_level {
+            0..=2 => RoomsAndCorridors,
+            3..=4 => Bsp,
+            5..=6 => Caves,
+            7..=8 => DrunkardsWalk,
+            // deeper than that, every floor is a surprise
+            level => [RoomsAndCorridors, Bsp, Caves, DrunkardsWalk][(level % 4) as usize],
+        }
+    }
+
+    pub fn generator(self) -> Box<dyn MapGenerator> {
+        use GeneratorKind::*;
+        match self {
+            RoomsAndCorridors => Box::new(rooms::RoomsAndCorridors::default()),
+            Bsp => Box::new(bsp::Bsp::default()),
+            Caves => Box::new(caves::Caves::default()),
+            DrunkardsWalk => Box::new(drunkard::DrunkardsWalk::default()),
+        }
+    }
+}
+
+fn random_monster(x: i32, y: i32, data: &GameData, dungeon_level: u32, rng: &mut GameRng) -> Option<Object> {
+    let name = data.spawn_tables.choose_monster(dungeon_level, rng)?;
+    data.monster(name).map(|template| template.spawn(x, y, rng))
+}
+
+fn random_item(x: i32, y: i32, data: &GameData, dungeon_level: u32, rng: &mut GameRng) -> Option<Object> {
+    let name = data.spawn_tables.choose_item(dungeon_level, rng)?;
+    data.item(name).map(|template| template.spawn(x, y))
+}
+
+fn place_objects(
+    area: &[(i32, i32)],
+    map: &Map,
+    objects: &mut Vec<Object>,
+    data: &GameData,
+    dungeon_level: u32,
+    rng: &mut GameRng,
+) {
+    if area.is_empty() {
+        return;
+    }
+    //placing monsters
+    let num_monsters = rng.gen_range(0, data.spawn_tables.max_monsters(dungeon_level) + 1);
+    for _ in 0..num_monsters {
+        let (x, y) = area[rng.gen_range(0, area.len())];
+        if !is_blocked(x, y, map, objects) {
+            objects.extend(random_monster(x, y, data, dungeon_level, rng));
+        }
+    }
+    //placing item
+    let num_items = rng.gen_range(0, data.spawn_tables.max_items(dungeon_level) + 1);
+    for _ in 0..num_items {
+        let (x, y) = area[rng.gen_range(0, area.len())];
+        if !is_blocked(x, y, map, objects) {
+            objects.extend(random_item(x, y, data, dungeon_level, rng));
+        }
+    }
+}
+
+pub(crate) fn create_room(room: Rect, map: &mut Map) {
+    for (x, y) in room.inner_points() {
+        map.set(x, y, Tile::empty());
+    }
+}
+
+pub(crate) fn create_h_tunnel(x1: i32, x2: i32, y: i32, map: &mut Map) {
+    for (x, y) in Line::new((x1, y), (x2, y)) {
+        map.set(x, y, Tile::empty());
+    }
+}
+
+pub(crate) fn create_v_tunnel(y1: i32, y2: i32, x: i32, map: &mut Map) {
+    for (x, y) in Line::new((x, y1), (x, y2)) {
+        map.set(x, y, Tile::empty());
+    }
+}
+
+// L shaped corridor between two points, the elbow side is random
+pub(crate) fn connect(from: (i32, i32), to: (i32, i32), map: &mut Map, rng: &mut GameRng) {
+    let ((prev_x, prev_y), (new_x, new_y)) = (from, to);
+    if rng.gen() {
+        create_h_tunnel(prev_x, new_x, prev_y, map);
+        create_v_tunnel(prev_y, new_y, new_x, map);
+    } else {
+        create_v_tunnel(prev_y, new_y, prev_x, map);
+        create_h_tunnel(prev_x, new_x, new_y, map);
+    }
+}
+
+pub(crate) fn room_floor(room: Rect, map: &Map) -> Vec<(i32, i32)> {
+    room.inner_points().filter(|&(x, y)| !map.is_blocked(x, y)).collect()
+}
+
+// Floor tiles cut in square chunks, for levels without rooms
+pub(crate) fn floor_chunks(map: &Map) -> Vec<Vec<(i32, i32)>> {
+    let mut chunks = vec![];
+    for chunk_x in (0..map.width()).step_by(SPAWN_AREA_SIZE as usize) {
+        for chunk_y in (0..map.height()).step_by(SPAWN_AREA_SIZE as usize) {
+            let chunk = Rect::new(chunk_x, chunk_y, SPAWN_AREA_SIZE - 1, SPAWN_AREA_SIZE - 1);
+            let floor: Vec<_> = chunk.points().filter(|&(x, y)| !map.is_blocked(x, y)).collect();
+            if !floor.is_empty() {
+                chunks.push(floor);
+            }
+        }
+    }
+    chunks
+}
+
+// Walking distance from `from` to every floor tile, None where it cannot be reached.
+// The player only walks in the 4 directions so diagonal gaps do not count.
+pub fn walk_distances(map: &Map, from: (i32, i32)) -> Vec<Option<i32>> {
+    let index = |(x, y): (i32, i32)| (x + y * map.width()) as usize;
+    let mut distances = vec![None; (map.width() * map.height()) as usize];
+    if map.is_blocked(from.0, from.1) {
+        return distances;
+    }
+    distances[index(from)] = Some(0);
+    let mut queue = VecDeque::new();
+    queue.push_back(from);
+    while let Some((x, y)) = queue.pop_front() {
+        let distance = distances[index((x, y))].unwrap_or(0);
+        for next in map.cardinal_neighbours(x, y) {
+            if !map.is_blocked(next.0, next.1) && distances[index(next)].is_none() {
+                distances[index(next)] = Some(distance + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    distances
+}
+
+// The reachable floor tile the furthest away from `from`, where the stairs go
+pub(crate) fn farthest_floor(map: &Map, from: (i32, i32)) -> (i32, i32) {
+    let distances = walk_distances(map, from);
+    map.positions()
+        .filter_map(|(x, y)| distances[(x + y * map.width()) as usize].map(|distance| (distance, (x, y))))
+        .max_by_key(|&(distance, _)| distance)
+        .map_or(from, |(_, pos)| pos)
+}
+
+// Dig a corridor from every floor pocket the player cannot reach to the
+// closest reachable tile, tiny pockets are simply filled
+fn repair_connectivity(level: &mut Level, rng: &mut GameRng) {
+    let width = level.map.width();
+    let index = |(x, y): (i32, i32)| (x + y * width) as usize;
+    loop {
+        let reachable = walk_distances(&level.map, level.start);
+        let orphan = level
+            .map
+            .positions()
+            .find(|&(x, y)| !level.map.is_blocked(x, y) && reachable[index((x, y))].is_none());
+        let orphan = match orphan {
+            Some(orphan) => orphan,
+            None => break,
+        };
+        let region = walk_distances(&level.map, orphan);
+        let region_tiles: Vec<_> = level
+            .map
+            .positions()
+            .filter(|&pos| region[index(pos)].is_some())
+            .collect();
+        if region_tiles.len() < MIN_REGION_SIZE {
+            for (x, y) in region_tiles {
+                level.map.set(x, y, Tile::wall());
+            }
+        } else {
+            let closest = level
+                .map
+                .positions()
+                .filter(|&pos| reachable[index(pos)].is_some())
+                .min_by_key(|&(x, y)| (x - orphan.0).abs() + (y - orphan.1).abs());
+            match closest {
+                Some(closest) => connect(orphan, closest, &mut level.map, rng),
+                None => break,
+            }
+        }
+    }
+    // filled pockets may have swallowed spawn points, vault markers included
+    let map = &level.map;
+    for area in level.spawn_areas.iter_mut() {
+        area.retain(|&(x, y)| !map.is_blocked(x, y));
+    }
+    level.monster_spawns.retain(|&(x, y)| !map.is_blocked(x, y));
+    level.item_spawns.retain(|&(x, y)| !map.is_blocked(x, y));
+}
+
+// Everything the player needs has to be walkable from where they start, and
+// nothing may be stuck in the rock
+fn is_valid(map: &Map, objects: &[Object]) -> bool {
+    let (start_x, start_y) = objects[PLAYER].pos();
+    let reachable = walk_distances(map, (start_x, start_y));
+    let is_reachable = |object: &Object| {
+        map.in_bounds(object.x, object.y)
+            && reachable[(object.x + object.y * map.width()) as usize].is_some()
+    };
+    let stairs = objects.iter().find(|object| object.stairs == Some(Stairs::Down));
+    !map.is_blocked(start_x, start_y)
+        && stairs.is_some_and(|stairs| stairs.pos() != (start_x, start_y) && is_reachable(stairs))
+        && objects.iter().filter(|object| object.item.is_some()).all(is_reachable)
+        && objects.iter().all(|object| !map.is_blocked(object.x, object.y))
+}
+
+// Last resort when no generator attempt worked, a single big room
+fn fallback_level() -> Level {
+    let mut map = Map::new(MAP_WIDTH, MAP_HEIGHT, Tile::wall());
+    let room = Rect::new(0, 0, MAP_WIDTH - 1, MAP_HEIGHT - 1);
+    create_room(room, &mut map);
+    Level {
+        spawn_areas: vec![room_floor(room, &map)],
+        map,
+        start: (room.x1 + 1, room.y1 + 1),
+        stairs: (room.x2 - 1, room.y2 - 1),
+        monster_spawns: vec![],
+        item_spawns: vec![],
+    }
+}
+
+fn populate(level: &Level, objects: &mut Vec<Object>, data: &GameData, dungeon_level: u32, rng: &mut GameRng) {
+    // Player is always the first object, everything else belongs to the previous level
+    objects.truncate(PLAYER + 1);
+    objects[PLAYER].set_pos(level.start.0, level.start.1);
+    for &(x, y) in &level.monster_spawns {
+        objects.extend(random_monster(x, y, data, dungeon_level, rng));
+    }
+    for &(x, y) in &level.item_spawns {
+        objects.extend(random_item(x, y, data, dungeon_level, rng));
+    }
+    for area in &level.spawn_areas {
+        place_objects(area, &level.map, objects, data, dungeon_level, rng);
+    }
+    // bosses stand guard on the way down
+    for boss in data.bosses.iter().filter(|boss| boss.dungeon_level == dungeon_level) {
+        if let Some((x, y)) = free_spot_near(level.stairs, &level.map, objects) {
+            objects.push(boss.spawn(x, y));
+        }
+    }
+
+    // Create Stairs, the way back up is where the player arrives
+    let (stairs_x, stairs_y) = level.stairs;
+    let mut stairs = Object::new(stairs_x, stairs_y, '<', WHITE, "stairs down", false);
+    stairs.always_visible = true;
+    stairs.stairs = Some(Stairs::Down);
+    objects.push(stairs);
+    if dungeon_level > 1 {
+        let (start_x, start_y) = level.start;
+        let mut stairs = Object::new(start_x, start_y, '>', WHITE, "stairs up", false);
+        stairs.always_visible = true;
+        stairs.stairs = Some(Stairs::Up);
+        objects.push(stairs);
+    }
+}
+
+pub fn make_map(objects: &mut Vec<Object>, rng: &mut GameRng, dungeon_level: u32, data: &GameData) -> Map {
+    let generator = GeneratorKind::for_level(dungeon_level).generator();
+    for _ in 0..MAX_GENERATION_ATTEMPTS {
+        if let Some(mut level) = generator.generate(rng) {
+            stamp_vaults(&mut level, &data.vaults, dungeon_level, rng);
+            repair_connectivity(&mut level, rng);
+            populate(&level, objects, data, dungeon_level, rng);
+            if is_valid(&level.map, objects) {
+                return level.map;
+            }
+        }
+    }
+    let level = fallback_level();
+    populate(&level, objects, data, dungeon_level, rng);
+    level.map
+}
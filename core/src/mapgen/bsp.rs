@@ -0,0 +1,90 @@
+//bsp.rs
+// Binary space partition: the map is cut in two again and again until the
+// pieces are small, a room is dug in every piece and sibling pieces are
+// joined by a corridor, so the whole level is always connected.
+use rand::Rng;
+
+use super::*;
+
+pub struct Bsp {
+    pub min_leaf_size: i32,
+    pub room_min_size: i32,
+}
+
+impl Default for Bsp {
+    fn default() -> Self {
+        Bsp {
+            min_leaf_size: ROOM_MAX_SIZE,
+            room_min_size: ROOM_MIN_SIZE - 2,
+        }
+    }
+}
+
+impl Bsp {
+    // Returns the rooms dug inside `leaf`, in the order they were dug
+    fn split(&self, leaf: Rect, map: &mut Map, rng: &mut GameRng) -> Vec<Rect> {
+        let width = leaf.x2 - leaf.x1;
+        let height = leaf.y2 - leaf.y1;
+        let can_split_x = width >= 2 * self.min_leaf_size;
+        let can_split_y = height >= 2 * self.min_leaf_size;
+        let split_x = match (can_split_x, can_split_y) {
+            (false, false) => return self.dig_room(leaf, map, rng).into_iter().collect(),
+            (true, false) => true,
+            (false, true) => false,
+            // prefer cutting the long side, rooms stay roughly square
+            (true, true) => width > height || (width == height && rng.gen()),
+        };
+        let (first, second) = if split_x {
+            let cut = rng.gen_range(leaf.x1 + self.min_leaf_size, leaf.x2 - self.min_leaf_size + 1);
+            (
+                Rect { x2: cut, ..leaf },
+                Rect { x1: cut, ..leaf },
+            )
+        } else {
+            let cut = rng.gen_range(leaf.y1 + self.min_leaf_size, leaf.y2 - self.min_leaf_size + 1);
+            (
+                Rect { y2: cut, ..leaf },
+                Rect { y1: cut, ..leaf },
+            )
+        };
+        let mut rooms = self.split(first, map, rng);
+        let second_rooms = self.split(second, map, rng);
+        if let (Some(last), Some(next)) = (rooms.last(), second_rooms.first()) {
+            connect(last.center(), next.center(), map, rng);
+        }
+        rooms.extend(second_rooms);
+        rooms
+    }
+
+    fn dig_room(&self, leaf: Rect, map: &mut Map, rng: &mut GameRng) -> Option<Rect> {
+        let max_w = leaf.x2 - leaf.x1 - 1;
+        let max_h = leaf.y2 - leaf.y1 - 1;
+        if max_w < self.room_min_size || max_h < self.room_min_size {
+            return None;
+        }
+        let w = rng.gen_range(self.room_min_size, max_w + 1);
+        let h = rng.gen_range(self.room_min_size, max_h + 1);
+        let x = rng.gen_range(leaf.x1, leaf.x2 - w);
+        let y = rng.gen_range(leaf.y1, leaf.y2 - h);
+        let room = Rect::new(x, y, w, h);
+        create_room(room, map);
+        Some(room)
+    }
+}
+
+impl MapGenerator for Bsp {
+    fn generate(&self, rng: &mut GameRng) -> Option<Level> {
+        let mut map = Map::new(MAP_WIDTH, MAP_HEIGHT, Tile::wall());
+        let rooms = self.split(Rect::new(0, 0, MAP_WIDTH - 1, MAP_HEIGHT - 1), &mut map, rng);
+        let start = rooms.first()?.center();
+        let stairs = rooms.last()?.center();
+        Some(Level {
+            spawn_areas: rooms.iter().map(|&room| room_floor(room, &map)).collect(),
+            map,
+            start,
+            stairs,
+            monster_spawns: vec![],
+            item_spawns: vec![],
+        })
+    }
+}
@@ -0,0 +1,95 @@
+//caves.rs
+// Cellular automata caves: random noise smoothed a few times, where a
+// tile becomes a wall when most of its neighbours are walls. Only the
+// biggest cave is kept so every floor tile can be reached.
+use rand::Rng;
+
+use super::*;
+
+pub struct Caves {
+    pub wall_chance: f32,
+    pub smoothing_steps: i32,
+}
+
+impl Default for Caves {
+    fn default() -> Self {
+        Caves {
+            wall_chance: 0.45,
+            smoothing_steps: 5,
+        }
+    }
+}
+
+impl Caves {
+    fn smooth(&self, map: &Map) -> Map {
+        let mut smoothed = map.clone();
+        for (x, y) in map.positions() {
+            let walls = map.neighbours(x, y).filter(|&(x, y)| map.is_blocked(x, y)).count()
+                // the outside of the map counts as wall
+                + (8 - map.neighbours(x, y).count());
+            let was_wall = map.is_blocked(x, y);
+            let tile = if walls >= 5 || (was_wall && walls >= 4) { Tile::wall() } else { Tile::empty() };
+            smoothed.set(x, y, tile);
+        }
+        smoothed
+    }
+}
+
+impl MapGenerator for Caves {
+    fn generate(&self, rng: &mut GameRng) -> Option<Level> {
+        let mut map = Map::new(MAP_WIDTH, MAP_HEIGHT, Tile::wall());
+        for (x, y) in map.positions() {
+            let border = x == 0 || y == 0 || x == MAP_WIDTH - 1 || y == MAP_HEIGHT - 1;
+            if !border && rng.gen::<f32>() >= self.wall_chance {
+                map.set(x, y, Tile::empty());
+            }
+        }
+        for _ in 0..self.smoothing_steps {
+            map = self.smooth(&map);
+        }
+
+        // keep the biggest cave, fill the others
+        let mut best_cave: Vec<(i32, i32)> = vec![];
+        let index = |(x, y): (i32, i32)| (x + y * MAP_WIDTH) as usize;
+        let mut visited = vec![false; (MAP_WIDTH * MAP_HEIGHT) as usize];
+        for (x, y) in map.positions() {
+            if map.is_blocked(x, y) || visited[index((x, y))] {
+                continue;
+            }
+            let distances = walk_distances(&map, (x, y));
+            let cave: Vec<_> = map
+                .positions()
+                .filter(|&pos| distances[index(pos)].is_some())
+                .collect();
+            for &(x, y) in &cave {
+                visited[index((x, y))] = true;
+            }
+            if cave.len() > best_cave.len() {
+                best_cave = cave;
+            }
+        }
+        let mut in_best_cave = vec![false; (MAP_WIDTH * MAP_HEIGHT) as usize];
+        for &pos in &best_cave {
+            in_best_cave[index(pos)] = true;
+        }
+        for (x, y) in map.positions() {
+            if !in_best_cave[index((x, y))] {
+                map.set(x, y, Tile::wall());
+            }
+        }
+
+        if best_cave.is_empty() {
+            return None;
+        }
+        let start = best_cave[rng.gen_range(0, best_cave.len())];
+        let stairs = farthest_floor(&map, start);
+        Some(Level {
+            spawn_areas: floor_chunks(&map),
+            map,
+            start,
+            stairs,
+            monster_spawns: vec![],
+            item_spawns: vec![],
+        })
+    }
+}
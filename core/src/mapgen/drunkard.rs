@@ -0,0 +1,58 @@
+//drunkard.rs
+// Drunkard's walk: diggers stumble around from already dug tiles until
+// enough of the map is floor. Everything they dig is connected.
+use rand::Rng;
+
+use super::*;
+
+pub struct DrunkardsWalk {
+    // part of the map that has to be floor before we stop digging
+    pub floor_ratio: f32,
+    pub steps_per_digger: i32,
+}
+
+impl Default for DrunkardsWalk {
+    fn default() -> Self {
+        DrunkardsWalk {
+            floor_ratio: 0.4,
+            steps_per_digger: 200,
+        }
+    }
+}
+
+impl MapGenerator for DrunkardsWalk {
+    fn generate(&self, rng: &mut GameRng) -> Option<Level> {
+        let mut map = Map::new(MAP_WIDTH, MAP_HEIGHT, Tile::wall());
+        let start = (MAP_WIDTH / 2, MAP_HEIGHT / 2);
+        map.set(start.0, start.1, Tile::empty());
+        let mut floor = vec![start];
+        let wanted_floor = ((MAP_WIDTH * MAP_HEIGHT) as f32 * self.floor_ratio) as usize;
+
+        while floor.len() < wanted_floor {
+            let (mut x, mut y) = floor[rng.gen_range(0, floor.len())];
+            for _ in 0..self.steps_per_digger {
+                let (dx, dy) = [(0, -1), (0, 1), (-1, 0), (1, 0)][rng.gen_range(0, 4)];
+                // the border stays wall
+                if x + dx <= 0 || y + dy <= 0 || x + dx >= MAP_WIDTH - 1 || y + dy >= MAP_HEIGHT - 1 {
+                    continue;
+                }
+                x += dx;
+                y += dy;
+                if map.is_blocked(x, y) {
+                    map.set(x, y, Tile::empty());
+                    floor.push((x, y));
+                }
+            }
+        }
+
+        let stairs = farthest_floor(&map, start);
+        Some(Level {
+            spawn_areas: floor_chunks(&map),
+            map,
+            start,
+            stairs,
+            monster_spawns: vec![],
+            item_spawns: vec![],
+        })
+    }
+}
@@ -0,0 +1,55 @@
+//rooms.rs
+// The original generator: random rooms that do not overlap, each one
+// linked to the previous one by a corridor.
+use rand::Rng;
+
+use super::*;
+
+pub struct RoomsAndCorridors {
+    pub max_rooms: i32,
+    pub room_min_size: i32,
+    pub room_max_size: i32,
+}
+
+impl Default for RoomsAndCorridors {
+    fn default() -> Self {
+        RoomsAndCorridors {
+            max_rooms: MAX_ROOMS,
+            room_min_size: ROOM_MIN_SIZE,
+            room_max_size: ROOM_MAX_SIZE,
+        }
+    }
+}
+
+impl MapGenerator for RoomsAndCorridors {
+    fn generate(&self, rng: &mut GameRng) -> Option<Level> {
+        let mut map = Map::new(MAP_WIDTH, MAP_HEIGHT, Tile::wall());
+        let mut rooms: Vec<Rect> = vec![];
+        for _ in 0..self.max_rooms {
+            let w = rng.gen_range(self.room_min_size, self.room_max_size + 1);
+            let h = rng.gen_range(self.room_min_size, self.room_max_size + 1);
+            let x = rng.gen_range(0, MAP_WIDTH - w);
+            let y = rng.gen_range(0, MAP_HEIGHT - h);
+            let new_room = Rect::new(x, y, w, h);
+            let failed = rooms.iter().any(|other_room| new_room.intersects_with(other_room));
+            if !failed {
+                create_room(new_room, &mut map);
+                if let Some(prev_room) = rooms.last() {
+                    connect(prev_room.center(), new_room.center(), &mut map, rng);
+                }
+                rooms.push(new_room);
+            }
+        }
+        // Player starts in the first room, stairs are in the last room
+        let start = rooms.first()?.center();
+        let stairs = rooms.last()?.center();
+        Some(Level {
+            spawn_areas: rooms.iter().map(|&room| room_floor(room, &map)).collect(),
+            map,
+            start,
+            stairs,
+            monster_spawns: vec![],
+            item_spawns: vec![],
+        })
+    }
+}
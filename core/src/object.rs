@@ -0,0 +1,341 @@
+// Object.rs
+use crate::boss::{boss_death, BossState};
+use crate::color::*;
+use crate::equipment::{self, Equipment};
+use serde::{Deserialize, Serialize};
+use crate::faction::Conversion;
+use crate::game::Game;
+use crate::pathfinding::Path;
+use crate::schedule::NORMAL_SPEED;
+use crate::status::{StatusEffect, StatusKind};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Object {
+    pub x: i32,
+    pub y: i32,
+    pub char: char,
+    pub color: Color,
+    pub name: String, 
+    pub blocks: bool,
+    pub alive: bool,
+    pub always_visible: bool,
+    pub fighter: Option<Fighter>,
+    pub ai: Option<Ai>,
+    pub item: Option<Item>,
+    pub equipment: Option<Equipment>,
+    // wands and staves, used again and again until they run dry
+    pub charges: Option<Charges>,
+    pub stairs: Option<Stairs>,
+    // where a monster is heading, kept between turns
+    pub path: Option<Path>,
+    pub senses: Option<Senses>,
+    // see data/factions.json, no faction means nobody's enemy
+    pub faction: Option<String>,
+    pub conversion: Option<Conversion>,
+    pub boss: Option<BossState>,
+    pub statuses: Vec<StatusEffect>,
+    // see schedule.rs
+    pub speed: i32,
+    pub energy: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Item {
+    Heal { amount: i32 },
+    Lightning { damage: i32, range: i32 },
+    // explodes on the tile aimed at, see area.rs
+    Fireball { damage: i32, radius: i32, range: i32 },
+    Confuse { range: i32, num_turns: i32 },
+    // drunk by the player, or thrown to shatter into a cloud
+    Potion { status: StatusKind, num_turns: i32 },
+    // worn or wielded, see the equipment component
+    Equipment,
+    // puts `amount` charges back into a wand or a staff
+    Recharge { amount: i32 },
+    // tells the true name of another item
+    Identify,
+    // the monster on the tile aimed at joins `faction`, for good without `num_turns`
+    Convert {
+        faction: String,
+        range: i32,
+        num_turns: Option<i32>,
+    },
+}
+
+impl Item {
+    // How far the items aimed at a tile reach, None for the others
+    pub fn range(&self) -> Option<i32> {
+        match *self {
+            Item::Lightning { range, .. }
+            | Item::Fireball { range, .. }
+            | Item::Confuse { range, .. }
+            | Item::Convert { range, .. } => Some(range),
+            _ => None,
+        }
+    }
+
+    // How far around the tile aimed at it reaches, 0 for a single target
+    pub fn radius(&self) -> i32 {
+        match *self {
+            Item::Fireball { radius, .. } => radius,
+            _ => 0,
+        }
+    }
+}
+
+// '<' leads one level deeper, '>' back to the level above
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Stairs {
+    Down,
+    Up,
+}
+
+pub enum UseResult {
+    UsedUp,
+    Kept,
+    Cancelled,
+}
+
+impl Object {
+    pub fn new(x: i32, y: i32, char: char, color: Color, name: &str, blocks: bool) -> Self {
+        Object {
+            x,
+            y,
+            char,
+            color,
+            name: name.into(),
+            blocks,
+            alive: false,
+            always_visible: false,
+            fighter: None,
+            ai: None,
+            item: None,
+            equipment: None,
+            charges: None,
+            stairs: None,
+            path: None,
+            senses: None,
+            faction: None,
+            conversion: None,
+            boss: None,
+            statuses: vec![],
+            speed: NORMAL_SPEED,
+            energy: 0,
+        }
+    }
+
+    pub fn pos(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    pub fn set_pos(&mut self, x: i32, y: i32) {
+        self.x = x;
+        self.y = y;
+    }
+
+    pub fn distance_to(&self, other: &Object) -> f32 {
+        let dx = other.x - self.x;
+        let dy = other.y - self.y;
+        ((dx.pow(2) + dy.pow(2)) as f32).sqrt()
+    }
+
+    pub fn distance(&self, x: i32, y: i32) -> f32 {
+        (((x - self.x).pow(2) + (y - self.y).pow(2)) as f32).sqrt()
+    }
+
+    pub fn has_status(&self, kind: StatusKind) -> bool {
+        self.status(kind).is_some()
+    }
+
+    pub fn status(&self, kind: StatusKind) -> Option<&StatusEffect> {
+        self.statuses.iter().find(|status| status.kind.same(&kind))
+    }
+
+    pub fn add_status(&mut self, kind: StatusKind, num_turns: i32) {
+        match self.statuses.iter_mut().find(|status| status.kind.same(&kind)) {
+            Some(status) => {
+                status.kind = kind;
+                status.num_turns = status.num_turns.max(num_turns);
+            }
+            None => self.statuses.push(StatusEffect { kind, num_turns }),
+        }
+    }
+
+    pub fn is_player(&self) -> bool {
+        self.fighter.is_some_and(|fighter| fighter.on_death == DeathCallBack::Player)
+    }
+
+    // Base stats plus what the player has equipped, monsters have no gear
+    pub fn power(&self, game: &Game) -> i32 {
+        self.fighter.map_or(0, |f| f.power) + self.bonus(game, |equipment| equipment.power_bonus)
+    }
+
+    pub fn defense(&self, game: &Game) -> i32 {
+        self.fighter.map_or(0, |f| f.defense) + self.bonus(game, |equipment| equipment.defense_bonus)
+    }
+
+    pub fn max_hp(&self, game: &Game) -> i32 {
+        self.fighter.map_or(0, |f| f.max_hp) + self.bonus(game, |equipment| equipment.max_hp_bonus)
+    }
+
+    fn bonus(&self, game: &Game, bonus: fn(Equipment) -> i32) -> i32 {
+        if self.is_player() {
+            equipment::equipped(game).map(bonus).sum()
+        } else {
+            0
+        }
+    }
+
+    pub fn take_damage(&mut self, damage: i32, game: &mut Game) {
+        if let Some(fighter) = self.fighter.as_mut() {
+            if damage > 0 {
+                fighter.hp -= damage;
+            }
+        }
+        if let Some(fighter) = self.fighter {
+            if fighter.hp <= 0 {
+                self.alive = false;
+                fighter.on_death.callback(self, game);
+            }
+        }
+    }
+
+    pub fn attack(&mut self, target: &mut Object, game: &mut Game) {
+        game.make_noise(self.pos());
+        let damage = self.power(game) - target.defense(game);
+        if damage > 0 {
+            game.messages.add(
+                format!(
+                    "{} attacks {} for {} hit points.",
+                    self.name, target.name, damage
+                    ),
+                    WHITE
+            );
+            target.take_damage(damage, game);
+        } else {
+            game.messages.add(
+                format!(
+                    "{} attacks {} but it has not effects!",
+                    self.name, target.name
+                    ),
+                    WHITE,
+            );
+
+        }
+    }
+
+    pub fn heal(&mut self, amount: i32, game: &Game) {
+        let max_hp = self.max_hp(game);
+        if let Some(ref mut fighter) = self.fighter {
+            fighter.hp += amount;
+            if fighter.hp > max_hp {
+                fighter.hp = max_hp;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Charges {
+    pub current: i32,
+    pub max: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fighter {
+    pub max_hp: i32,
+    pub hp: i32,
+    pub defense: i32,
+    pub power: i32,
+    // percent of the hit points a monster can lose before it runs, 100 never runs
+    pub morale: i32,
+    pub on_death: DeathCallBack,
+}
+
+impl Fighter {
+    pub fn is_broken(&self) -> bool {
+        self.hp * 100 < self.max_hp * (100 - self.morale)
+    }
+}
+
+// define AI type for monster
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Ai {
+    Basic,
+    // archers and casters, shoot from afar and keep out of reach
+    Ranged {
+        range: i32,
+        // percent chance to hit each creature the shot flies by
+        accuracy: i32,
+    },
+    // running from the player until healed, or cornered
+    Fleeing {
+        previous_ai: Box<Ai>,
+        num_turns: i32,
+    },
+}
+
+
+// How a monster finds out about the player, and what it makes of it
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Senses {
+    pub sight_radius: i32,
+    // fights closer than that wake it up
+    pub hearing: i32,
+    pub awareness: Awareness,
+    // wanders to the nearest loot and stands guard over it
+    #[serde(default)]
+    pub greedy: bool,
+    // game clock of its last turn, the noises since then are news
+    #[serde(default)]
+    pub last_turn: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Awareness {
+    Asleep,
+    Wandering,
+    // after the player, or where it last saw or heard them
+    Hunting { last_known: (i32, i32) },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DeathCallBack {
+    Player, 
+    Monster,
+    // see the on_death effects in data/bosses.json
+    Boss,
+}
+
+impl DeathCallBack {
+    fn callback(self, object: &mut Object, game: &mut Game) {
+        use DeathCallBack::*;
+        let callback = match self {
+            Player => player_death,
+            Monster => monster_death,
+            Boss => boss_death,
+        };
+        callback(object, game);
+    }
+}
+
+fn player_death(player: &mut Object, game: &mut Game) {
+    game.messages.add("You died!", RED);
+    player.char = '%';
+    player.color = DARK_RED;
+}
+
+pub(crate) fn monster_death(monster: &mut Object, game: &mut Game) {
+    game.messages.add(format!("{} is dead", monster.name), GREEN);
+    monster.char = '%';
+    monster.color = DARK_RED;
+    monster.blocks = false;
+    monster.fighter = None;
+    monster.ai = None;
+    monster.path = None;
+    monster.senses = None;
+    monster.conversion = None;
+    monster.statuses.clear();
+    monster.name = format!("Remains of {}", monster.name);
+}
@@ -0,0 +1,131 @@
+//pathfinding.rs
+// A* over the map for monsters on the hunt. Other creatures are soft
+// obstacles: walking through them costs more, so a monster queues behind a
+// friend in a corridor but goes around it when the room is wide open.
+use std::cmp::{self, Reverse};
+use std::collections::BinaryHeap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::map::Map;
+use crate::object::Object;
+
+// Extra cost of a step onto a tile some creature is standing on
+const SOFT_OBSTACLE_COST: i32 = 8;
+
+// A path kept by a monster between turns
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Path {
+    goal: (i32, i32),
+    // next step last
+    steps: Vec<(i32, i32)>,
+    // steps that had a blocker on them when the path was found
+    crowded: Vec<(i32, i32)>,
+}
+
+impl Path {
+    pub fn find(from: (i32, i32), goal: (i32, i32), map: &Map, objects: &[Object]) -> Option<Path> {
+        let cost = |x: i32, y: i32| {
+            if (x, y) != goal && occupied(x, y, from, objects) {
+                1 + SOFT_OBSTACLE_COST
+            } else {
+                1
+            }
+        };
+        let mut steps = astar(map, from, goal, cost)?;
+        steps.reverse();
+        let crowded = steps
+            .iter()
+            .cloned()
+            .filter(|&(x, y)| (x, y) != goal && occupied(x, y, from, objects))
+            .collect();
+        Some(Path { goal, steps, crowded })
+    }
+
+    // Still worth following: same goal, the next step is right next to the
+    // walker, and no blocker stepped on or off the way since it was found
+    pub fn is_valid(&self, from: (i32, i32), goal: (i32, i32), objects: &[Object]) -> bool {
+        if self.goal != goal {
+            return false;
+        }
+        let next = match self.next_step() {
+            Some(next) => next,
+            None => return false,
+        };
+        if cmp::max((next.0 - from.0).abs(), (next.1 - from.1).abs()) != 1 {
+            return false;
+        }
+        self.steps
+            .iter()
+            .filter(|&&step| step != goal)
+            .all(|&(x, y)| occupied(x, y, from, objects) == self.crowded.contains(&(x, y)))
+    }
+
+    pub fn next_step(&self) -> Option<(i32, i32)> {
+        self.steps.last().cloned()
+    }
+
+    // The walker made it to the next step
+    pub fn advance(&mut self) {
+        self.steps.pop();
+    }
+}
+
+fn occupied(x: i32, y: i32, walker: (i32, i32), objects: &[Object]) -> bool {
+    objects
+        .iter()
+        .any(|object| object.blocks && object.pos() == (x, y) && object.pos() != walker)
+}
+
+// Shortest way from `from` to `goal` moving in 8 directions, `cost` is the
+// price of stepping on a floor tile. The steps exclude `from` and end on `goal`.
+pub fn astar<F>(map: &Map, from: (i32, i32), goal: (i32, i32), cost: F) -> Option<Vec<(i32, i32)>>
+where
+    F: Fn(i32, i32) -> i32,
+{
+    if !map.in_bounds(from.0, from.1) || map.is_blocked(goal.0, goal.1) {
+        return None;
+    }
+    if from == goal {
+        return Some(vec![]);
+    }
+    let index = |(x, y): (i32, i32)| (x + y * map.width()) as usize;
+    let heuristic = |(x, y): (i32, i32)| cmp::max((goal.0 - x).abs(), (goal.1 - y).abs());
+    let size = (map.width() * map.height()) as usize;
+    let mut best_cost = vec![i32::MAX; size];
+    let mut came_from: Vec<Option<(i32, i32)>> = vec![None; size];
+    let mut open = BinaryHeap::new();
+
+    best_cost[index(from)] = 0;
+    open.push(Reverse((heuristic(from), 0, from)));
+    while let Some(Reverse((_, spent, pos))) = open.pop() {
+        if pos == goal {
+            let mut steps = vec![goal];
+            let mut current = goal;
+            while let Some(previous) = came_from[index(current)] {
+                if previous == from {
+                    break;
+                }
+                steps.push(previous);
+                current = previous;
+            }
+            steps.reverse();
+            return Some(steps);
+        }
+        if spent > best_cost[index(pos)] {
+            continue;
+        }
+        for (x, y) in map.neighbours(pos.0, pos.1) {
+            if map.is_blocked(x, y) {
+                continue;
+            }
+            let new_cost = spent + cost(x, y);
+            if new_cost < best_cost[index((x, y))] {
+                best_cost[index((x, y))] = new_cost;
+                came_from[index((x, y))] = Some(pos);
+                open.push(Reverse((new_cost + heuristic((x, y)), new_cost, (x, y))));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,64 @@
+//replay.rs
+// A run is fully described by its seed and the commands the player gave,
+// replaying them through `take_turn` rebuilds the exact same game.
+use std::error::Error;
+use std::fs::File;
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::GameData;
+use crate::game::Game;
+use crate::object::Object;
+use crate::rules::{new_game, take_turn, Command};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub commands: Vec<Command>,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Replay {
+            seed,
+            commands: vec![],
+        }
+    }
+
+    pub fn record(&mut self, command: Command) {
+        self.commands.push(command);
+    }
+
+    // Fresh game at the seed of the replay, ready for `step`. The data has
+    // to be the one the run was recorded with.
+    pub fn start(&self, data: GameData) -> (Game, Vec<Object>) {
+        new_game(self.seed, data)
+    }
+
+    // Play the command number `index`, false once the replay is over
+    pub fn step(&self, index: usize, game: &mut Game, objects: &mut Vec<Object>) -> bool {
+        match self.commands.get(index) {
+            Some(&command) => {
+                take_turn(command, game, objects);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let replay_data = serde_json::to_string(self)?;
+        let mut file = File::create(path)?;
+        file.write_all(replay_data.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: &str) -> Result<Replay, Box<dyn Error>> {
+        let mut json_replay = String::new();
+        let mut file = File::open(path)?;
+        file.read_to_string(&mut json_replay)?;
+        let replay = serde_json::from_str::<Replay>(&json_replay)?;
+        Ok(replay)
+    }
+}
@@ -0,0 +1,48 @@
+//rng.rs
+// The one random number generator of a game. Every random roll goes through
+// `Game.rng` so the same seed and the same inputs always replay the same way,
+// and its state is part of the savegame.
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// xorshift128, same algorithm as rand::XorShiftRng but serializable
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameRng {
+    state: [u32; 4],
+}
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        // splitmix64 spreads the seed so close seeds give unrelated games
+        let mut mix = seed;
+        let mut next = || {
+            mix = mix.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = mix;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        };
+        let (a, b) = (next(), next());
+        let mut state = [a as u32, (a >> 32) as u32, b as u32, (b >> 32) as u32];
+        // xorshift never leaves the all zero state
+        if state == [0; 4] {
+            state[0] = 1;
+        }
+        GameRng { state }
+    }
+}
+
+impl Rng for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        let [x, y, z, w] = self.state;
+        let t = x ^ (x << 11);
+        let new_w = w ^ (w >> 19) ^ (t ^ (t >> 8));
+        self.state = [y, z, w, new_w];
+        new_w
+    }
+}
+
+// Seed for a brand new game, the only roll that is not reproducible
+pub fn random_seed() -> u64 {
+    rand::random::<u64>() % 1_000_000_000
+}
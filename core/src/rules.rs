@@ -0,0 +1,399 @@
+//rules.rs
+// Turn resolution: front ends translate their input into a `Command`
+// and hand it to `take_turn`, which lets the monsters answer.
+use std::cmp;
+use std::collections::BTreeMap;
+use std::mem;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::ai::ai_take_turn;
+use crate::area::tick_clouds;
+use crate::color::*;
+use crate::data::GameData;
+use crate::dijkstra::AiMaps;
+use crate::faction::{is_companion, PLAYER_FACTION};
+use crate::equipment::{equip, unequip};
+use crate::game::*;
+use crate::identify::Identification;
+use crate::items::*;
+use crate::map::{Line, Map};
+use crate::mapgen::{make_map, walk_distances};
+use crate::object::*;
+use crate::rng::GameRng;
+use crate::schedule::{next_actor, ACTION_COST};
+use crate::status::{tick_statuses, StatusKind};
+use crate::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PlayerAction {
+    TookTurn,
+    DidntTakeTurn,
+    Exit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Command {
+    Move(i32, i32),
+    PickUp,
+    // with the tile aimed at, for the items that have a range
+    UseItem(usize, Option<(i32, i32)>),
+    DropItem(usize),
+    // potions shatter where they land
+    Throw(usize, Option<(i32, i32)>),
+    Equip(usize),
+    Unequip(usize),
+    // a scroll read on another item: recharge, identify
+    UseOn(usize, usize),
+    Descend,
+    Ascend,
+}
+
+pub fn new_game(seed: u64, data: GameData) -> (Game, Vec<Object>) {
+    // Initialize player
+    let mut player = Object::new(0, 0, '@', WHITE, "player", true);
+    player.alive = true;
+    player.faction = Some(PLAYER_FACTION.into());
+    player.fighter = Some(Fighter {
+        max_hp: PLAYER_BASE_MAX_HP,
+        hp: PLAYER_BASE_MAX_HP,
+        defense: PLAYER_BASE_DEFENSE,
+        power: PLAYER_BASE_POWER,
+        morale: 100,
+        on_death: DeathCallBack::Player,
+    });
+    // the first move is the player's
+    player.energy = ACTION_COST;
+    // Add player to object list
+    let mut objects = vec![player];
+
+    let mut rng = GameRng::from_seed(seed);
+    let map = make_map(&mut objects, &mut rng, 1, &data);
+    let identification = Identification::new(&data, &mut rng);
+    let mut game = Game {
+        map,
+        messages: Messages::new(),
+        inventory: vec![],
+        dungeon_level: 1,
+        levels: BTreeMap::new(),
+        clouds: vec![],
+        identification,
+        seed,
+        rng,
+        clock: 0,
+        fov: Default::default(),
+        ai_maps: Default::default(),
+        noises: vec![],
+        spawned: vec![],
+        data,
+    };
+    update_fov(&mut game, &objects);
+
+    game.messages.add(
+        "Welcome Stranger ! Prepare to perish in the Maze of the Blue Medusa",
+        RED,
+        );
+    (game, objects)
+}
+
+pub fn take_turn(command: Command, game: &mut Game, objects: &mut Vec<Object>) -> PlayerAction {
+    use PlayerAction::*;
+    game.clock += 1;
+    forget_heard_noises(game, objects);
+    let player_action = match command {
+        // whatever the player wanted to do, the turn goes by
+        _ if objects[PLAYER].has_status(StatusKind::Paralysed) => {
+            game.messages.add("You cannot move!", LIGHT_BLUE);
+            TookTurn
+        }
+        Command::Move(dx, dy) => {
+            let (dx, dy) = if objects[PLAYER].has_status(StatusKind::Confused) {
+                (game.rng.gen_range(-1, 2), game.rng.gen_range(-1, 2))
+            } else {
+                (dx, dy)
+            };
+            // stumbling on the spot
+            if (dx, dy) != (0, 0) {
+                player_move_or_attack(dx, dy, game, objects);
+            }
+            TookTurn
+        }
+        Command::PickUp => {
+            let item_id = objects
+                .iter()
+                .position(|object| object.pos() == objects[PLAYER].pos() && object.item.is_some());
+            if let Some(item_id) = item_id {
+                pick_item_up(item_id, game, objects);
+            }
+            DidntTakeTurn
+        }
+        Command::UseItem(inventory_index, target) => {
+            took_turn(use_item(inventory_index, target, game, objects))
+        }
+        Command::DropItem(inventory_index) => {
+            drop_item(inventory_index, game, objects);
+            DidntTakeTurn
+        }
+        Command::Throw(inventory_index, target) => {
+            took_turn(throw_item(inventory_index, target, game, objects))
+        }
+        Command::Equip(inventory_index) => {
+            took_turn(equip(inventory_index, game, objects))
+        }
+        Command::Unequip(inventory_index) => {
+            took_turn(unequip(inventory_index, game, objects))
+        }
+        Command::UseOn(scroll_index, target_index) => {
+            took_turn(use_on(scroll_index, target_index, game, objects))
+        }
+        Command::Descend => {
+            if player_on_stairs(Stairs::Down, objects) {
+                next_level(game, objects);
+            }
+            DidntTakeTurn
+        }
+        Command::Ascend => {
+            if player_on_stairs(Stairs::Up, objects) {
+                previous_level(game, objects);
+            }
+            DidntTakeTurn
+        }
+    };
+    update_fov(game, objects);
+    if objects[PLAYER].alive && player_action == TookTurn {
+        objects[PLAYER].energy -= ACTION_COST;
+        tick_statuses(PLAYER, game, objects);
+        tick_clouds(game, objects);
+        game.ai_maps = AiMaps::compute(&game.map, objects);
+        // the monsters act until the player has the energy to act again
+        loop {
+            let id = next_actor(objects);
+            if id == PLAYER || !objects[PLAYER].alive {
+                break;
+            }
+            objects[id].energy -= ACTION_COST;
+            game.clock += 1;
+            ai_take_turn(id, game, objects);
+        }
+        // blindness may have worn off
+        update_fov(game, objects);
+    }
+    objects.append(&mut game.spawned);
+    player_action
+}
+
+// A noise lasts until every monster around had a turn to hear it, the
+// ones who acted before the fight broke out included
+fn forget_heard_noises(game: &mut Game, objects: &[Object]) {
+    let last_turns: Vec<u64> = objects
+        .iter()
+        .filter(|object| object.alive)
+        .filter_map(|object| object.senses)
+        .map(|senses| senses.last_turn)
+        .collect();
+    game.noises
+        .retain(|noise| last_turns.iter().any(|&last_turn| last_turn < noise.made_at));
+}
+
+fn took_turn(acted: bool) -> PlayerAction {
+    if acted {
+        PlayerAction::TookTurn
+    } else {
+        PlayerAction::DidntTakeTurn
+    }
+}
+
+pub fn update_fov(game: &mut Game, objects: &[Object]) {
+    let (player_x, player_y) = objects[PLAYER].pos();
+    let radius = if objects[PLAYER].has_status(StatusKind::Blinded) {
+        BLINDED_RADIUS
+    } else {
+        TORCH_RADIUS
+    };
+    game.fov.compute(&game.map, player_x, player_y, radius, FOV_LIGHT_WALLS);
+    for (x, y) in game.map.positions() {
+        if game.fov.is_in_fov(x, y) {
+            if let Some(tile) = game.map.get_mut(x, y) {
+                tile.explored = true;
+            }
+        }
+    }
+}
+
+fn player_on_stairs(stairs: Stairs, objects: &[Object]) -> bool {
+    objects
+        .iter()
+        .any(|object| object.pos() == objects[PLAYER].pos() && object.stairs == Some(stairs))
+}
+
+pub fn next_level(game: &mut Game, objects: &mut Vec<Object>) {
+    let dungeon_level = game.dungeon_level + 1;
+    if game.levels.contains_key(&dungeon_level) {
+        game.messages.add(format!("You descend back to level {}", dungeon_level), VIOLET);
+    } else {
+        game.messages.add(
+            "You take a moment to rest, and recover your strength",
+            VIOLET,
+            );
+        let heal_hp = objects[PLAYER].max_hp(game) / 2;
+        objects[PLAYER].heal(heal_hp, game);
+        game.messages.add(
+            "After a rare moment of peace, you descend deeper into the hearth of the dungeon...",
+            RED,
+            );
+    }
+    change_level(dungeon_level, game, objects);
+}
+
+pub fn previous_level(game: &mut Game, objects: &mut Vec<Object>) {
+    let dungeon_level = game.dungeon_level - 1;
+    game.messages.add(format!("You climb back up to level {}", dungeon_level), VIOLET);
+    change_level(dungeon_level, game, objects);
+}
+
+// Store the current level as it is and bring back (or dig) `dungeon_level`,
+// the player arrives on the stairs leading back where they came from
+fn change_level(dungeon_level: u32, game: &mut Game, objects: &mut Vec<Object>) {
+    let going_down = dungeon_level > game.dungeon_level;
+    let (followers, mut left_objects): (Vec<Object>, Vec<Object>) = objects
+        .split_off(PLAYER + 1)
+        .into_iter()
+        .partition(|object| is_companion(object) && object.distance_to(&objects[PLAYER]) <= FOLLOW_STAIRS_DISTANCE);
+    let (map, clouds) = match game.levels.remove(&dungeon_level) {
+        Some(level) => {
+            objects.extend(level.objects);
+            (level.map, level.clouds)
+        }
+        None => (make_map(objects, &mut game.rng, dungeon_level, &game.data), vec![]),
+    };
+    let left_map = mem::replace(&mut game.map, map);
+    let left_clouds = mem::replace(&mut game.clouds, clouds);
+
+    let arrival = if going_down { Stairs::Up } else { Stairs::Down };
+    let arrival_pos = objects
+        .iter()
+        .find(|object| object.stairs == Some(arrival))
+        .map(|object| object.pos());
+    if let Some((x, y)) = arrival_pos {
+        objects[PLAYER].set_pos(x, y);
+    }
+    for mut follower in followers {
+        match free_spot_near(objects[PLAYER].pos(), &game.map, objects) {
+            Some((x, y)) => {
+                game.messages.add(format!("The {} follows you", follower.name), LIGHT_GREEN);
+                follower.set_pos(x, y);
+                follower.path = None;
+                objects.push(follower);
+            }
+            // no room by the stairs, it waits where it was
+            None => left_objects.push(follower),
+        }
+    }
+    game.levels.insert(
+        game.dungeon_level,
+        StoredLevel {
+            map: left_map,
+            objects: left_objects,
+            clouds: left_clouds,
+        },
+    );
+    game.dungeon_level = dungeon_level;
+    // the fights on the level left behind are out of earshot
+    game.noises.clear();
+    update_fov(game, objects);
+}
+
+// The closest free floor tile that can be walked to from `from`
+pub(crate) fn free_spot_near(from: (i32, i32), map: &Map, objects: &[Object]) -> Option<(i32, i32)> {
+    let distances = walk_distances(map, from);
+    map.positions()
+        .filter(|&(x, y)| !is_blocked(x, y, map, objects))
+        .filter_map(|(x, y)| distances[(x + y * map.width()) as usize].map(|distance| (distance, (x, y))))
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, pos)| pos)
+}
+
+pub fn move_by(id: usize, dx: i32, dy: i32, map: &Map, objects: &mut [Object]) {
+    let (x, y) = objects[id].pos();
+    if !is_blocked(x + dx, y + dy, map, objects) {
+        objects[id].set_pos(x + dx, y + dy);
+    }
+}
+
+pub fn player_move_or_attack(dx: i32, dy: i32, game: &mut Game, objects: &mut [Object]) {
+    let x = objects[PLAYER].x + dx;
+    let y = objects[PLAYER].y + dy;
+
+    let target_id = objects
+        .iter()
+        .position(|object| object.fighter.is_some() && object.pos() == (x, y));
+
+    match target_id {
+        // companions make way
+        Some(target_id) if is_companion(&objects[target_id]) => {
+            let (player_x, player_y) = objects[PLAYER].pos();
+            objects[target_id].set_pos(player_x, player_y);
+            objects[PLAYER].set_pos(x, y);
+        }
+        Some(target_id) => {
+            let (player, target) = mut_two(PLAYER, target_id, objects);
+            player.attack(target, game);
+        }
+        None => {
+            move_by(PLAYER, dx, dy, &game.map, objects);
+        }
+    }
+
+}
+
+pub fn is_blocked(x: i32, y: i32, map: &Map, objects: &[Object]) -> bool {
+    if map.is_blocked(x, y) {
+        return true;
+    }
+    objects.iter().any(|object| object.blocks && object.pos() == (x,y))
+}
+
+pub fn mut_two<T>(first_index: usize, second_index: usize, items: &mut [T]) -> (&mut T, &mut T) {
+    assert!(first_index != second_index);
+    let split_at_index = cmp::max(first_index, second_index);
+    let (first_slice, seconde_slice) = items.split_at_mut(split_at_index);
+    if first_index < second_index {
+        (&mut first_slice[first_index], &mut seconde_slice[0])
+    } else {
+        (&mut seconde_slice[0], &mut first_slice[second_index])
+    }
+}
+
+// Tiles a shot from `from` aimed at `to` flies over, the target included,
+// and on past it until `range` is reached
+pub fn flight_path(from: (i32, i32), to: (i32, i32), range: i32) -> Vec<(i32, i32)> {
+    let beyond = (to.0 + (to.0 - from.0) * range, to.1 + (to.1 - from.1) * range);
+    Line::new(from, to)
+        .skip(1)
+        .chain(Line::new(to, beyond).skip(1))
+        .take_while(|&(x, y)| (x - from.0).pow(2) + (y - from.1).pow(2) <= range.pow(2))
+        .collect()
+}
+
+// Nothing between `from` and `to` blocks the view
+pub fn line_of_sight(from: (i32, i32), to: (i32, i32), map: &Map) -> bool {
+    Line::new(from, to)
+        .skip(1)
+        .take_while(|&pos| pos != to)
+        .all(|(x, y)| !map.blocks_sight(x, y))
+}
+
+// No wall between `from` and `to` stops a shot. Creatures in the way may
+// catch it instead, see ai.rs
+pub fn line_of_fire(from: (i32, i32), to: (i32, i32), range: i32, map: &Map) -> bool {
+    for (x, y) in flight_path(from, to, range) {
+        if (x, y) == to {
+            return true;
+        }
+        if map.blocks_sight(x, y) {
+            return false;
+        }
+    }
+    false
+}
@@ -0,0 +1,29 @@
+//save.rs
+use std::error::Error;
+use std::fs::File;
+use std::io::{Read, Write};
+
+use crate::data::GameData;
+use crate::game::Game;
+use crate::object::Object;
+use crate::replay::Replay;
+use crate::rules::update_fov;
+
+// The replay of the run so far goes with the save, so that a continued game
+// keeps recording from where it stopped
+pub fn save_game(game: &Game, objects: &[Object], replay: &Replay) -> Result<(), Box<dyn Error>> {
+    let save_data = serde_json::to_string(&(game, objects, replay))?;
+    let mut file = File::create("savegame")?;
+    file.write_all(save_data.as_bytes())?;
+    Ok(())
+}
+
+pub fn load_game(data: GameData) -> Result<(Game, Vec<Object>, Replay), Box<dyn Error>> {
+    let mut json_save_state = String::new();
+    let mut file = File::open("savegame")?;
+    file.read_to_string(&mut json_save_state)?;
+    let (mut game, objects, replay) = serde_json::from_str::<(Game, Vec<Object>, Replay)>(&json_save_state)?;
+    game.data = data;
+    update_fov(&mut game, &objects);
+    Ok((game, objects, replay))
+}
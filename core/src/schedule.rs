@@ -0,0 +1,47 @@
+//schedule.rs
+// Who acts next. Creatures build up energy as time goes by, as fast as
+// their speed, and spend ACTION_COST of it on every action. Ties go to the
+// first one in the object list, the player before anybody else, so the same
+// commands always play out the same way.
+use crate::object::Object;
+use crate::status::StatusKind;
+use crate::PLAYER;
+
+// Energy an action costs, a creature of normal speed gets it in 10 ticks
+pub const ACTION_COST: i32 = 1000;
+pub const NORMAL_SPEED: i32 = 100;
+
+// Energy a tick of time gives, haste doubles it and slowness halves it
+pub fn speed(object: &Object) -> i32 {
+    let mut speed = object.speed;
+    if object.has_status(StatusKind::Hasted) {
+        speed *= 2;
+    }
+    if object.has_status(StatusKind::Slowed) {
+        speed /= 2;
+    }
+    speed.max(1)
+}
+
+// The player, and the monsters still up and about
+fn takes_turns(id: usize, object: &Object) -> bool {
+    id == PLAYER || (object.alive && object.ai.is_some())
+}
+
+// Let time pass until some creature has the energy to act, which one
+pub fn next_actor(objects: &mut [Object]) -> usize {
+    loop {
+        let ready = objects
+            .iter()
+            .enumerate()
+            .position(|(id, object)| takes_turns(id, object) && object.energy >= ACTION_COST);
+        if let Some(id) = ready {
+            return id;
+        }
+        for (id, object) in objects.iter_mut().enumerate() {
+            if takes_turns(id, object) {
+                object.energy += speed(object);
+            }
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//spawn.rs
+// What shows up on a level depends on how deep it is. Every number of the
+// spawn tables is a list of transitions: the value of the last transition
+// at or above the current dungeon level, 0 before the first one.
+// The monsters and items themselves are templates read from the data files.
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::color::Color;
+use crate::equipment::Equipment;
+use crate::object::*;
+use crate::rng::GameRng;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Transition {
+    pub level: u32,
+    pub value: u32,
+}
+
+pub fn from_dungeon_level(table: &[Transition], dungeon_level: u32) -> u32 {
+    table
+        .iter()
+        .rev()
+        .find(|transition| dungeon_level >= transition.level)
+        .map_or(0, |transition| transition.value)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnEntry {
+    pub name: String,
+    pub weight: Vec<Transition>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnTables {
+    pub monsters_per_room: Vec<Transition>,
+    pub items_per_room: Vec<Transition>,
+    pub monsters: Vec<SpawnEntry>,
+    pub items: Vec<SpawnEntry>,
+}
+
+impl SpawnTables {
+    pub fn max_monsters(&self, dungeon_level: u32) -> u32 {
+        from_dungeon_level(&self.monsters_per_room, dungeon_level)
+    }
+
+    pub fn max_items(&self, dungeon_level: u32) -> u32 {
+        from_dungeon_level(&self.items_per_room, dungeon_level)
+    }
+
+    pub fn choose_monster(&self, dungeon_level: u32, rng: &mut GameRng) -> Option<&str> {
+        choose(&self.monsters, dungeon_level, rng)
+    }
+
+    pub fn choose_item(&self, dungeon_level: u32, rng: &mut GameRng) -> Option<&str> {
+        choose(&self.items, dungeon_level, rng)
+    }
+
+    // Every name must be one of the templates
+    pub fn validate(&self, monsters: &[MonsterTemplate], items: &[ItemTemplate]) -> Result<(), String> {
+        check_transitions("monsters_per_room", &self.monsters_per_room)?;
+        check_transitions("items_per_room", &self.items_per_room)?;
+        for (index, entry) in self.monsters.iter().enumerate() {
+            let entry_name = format!("monsters #{} \"{}\"", index, entry.name);
+            check_transitions(&entry_name, &entry.weight)?;
+            if !monsters.iter().any(|monster| monster.name == entry.name) {
+                return Err(format!("{}: no such monster in monsters.json", entry_name));
+            }
+        }
+        for (index, entry) in self.items.iter().enumerate() {
+            let entry_name = format!("items #{} \"{}\"", index, entry.name);
+            check_transitions(&entry_name, &entry.weight)?;
+            if !items.iter().any(|item| item.name == entry.name) {
+                return Err(format!("{}: no such item in items.json", entry_name));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_transitions(name: &str, table: &[Transition]) -> Result<(), String> {
+    if table.windows(2).any(|pair| pair[0].level >= pair[1].level) {
+        return Err(format!("{}: transitions must go deeper and deeper", name));
+    }
+    Ok(())
+}
+
+// Weighted pick among the entries that can show up at this depth
+fn choose<'a>(entries: &'a [SpawnEntry], dungeon_level: u32, rng: &mut GameRng) -> Option<&'a str> {
+    let weights: Vec<u32> = entries
+        .iter()
+        .map(|entry| from_dungeon_level(&entry.weight, dungeon_level))
+        .collect();
+    let total: u32 = weights.iter().sum();
+    if total == 0 {
+        return None;
+    }
+    let mut dice = rng.gen_range(0, total);
+    for (entry, weight) in entries.iter().zip(weights) {
+        if dice < weight {
+            return Some(&entry.name);
+        }
+        dice -= weight;
+    }
+    None
+}
+
+// ===================== TEMPLATES
+// One entry of monsters.json
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MonsterTemplate {
+    pub name: String,
+    pub glyph: char,
+    pub color: Color,
+    pub max_hp: i32,
+    pub defense: i32,
+    pub power: i32,
+    pub morale: i32,
+    pub faction: String,
+    pub ai: Ai,
+    pub sight_radius: i32,
+    pub hearing: i32,
+    // 100 is the player's, see schedule.rs
+    pub speed: i32,
+    // percent chance to be found asleep, wandering around otherwise
+    pub sleep_chance: i32,
+    #[serde(default)]
+    pub greedy: bool,
+}
+
+impl MonsterTemplate {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_hp <= 0 {
+            return Err("max_hp must be positive".into());
+        }
+        if self.defense < 0 || self.power < 0 {
+            return Err("defense and power cannot be negative".into());
+        }
+        if !(0..=100).contains(&self.morale) {
+            return Err("morale goes from 0 to 100".into());
+        }
+        if self.sight_radius < 1 || self.hearing < 0 {
+            return Err("sight_radius must be positive and hearing cannot be negative".into());
+        }
+        if self.speed <= 0 {
+            return Err("speed must be positive".into());
+        }
+        if !(0..=100).contains(&self.sleep_chance) {
+            return Err("sleep_chance goes from 0 to 100".into());
+        }
+        validate_ai(&self.ai)
+    }
+
+    pub fn spawn(&self, x: i32, y: i32, rng: &mut GameRng) -> Object {
+        let mut monster = Object::new(x, y, self.glyph, self.color, &self.name, true);
+        monster.alive = true;
+        monster.fighter = Some(Fighter {
+            max_hp: self.max_hp,
+            hp: self.max_hp,
+            defense: self.defense,
+            power: self.power,
+            morale: self.morale,
+            on_death: DeathCallBack::Monster,
+        });
+        monster.ai = Some(self.ai.clone());
+        monster.faction = Some(self.faction.clone());
+        monster.speed = self.speed;
+        let awareness = if rng.gen_range(0, 100) < self.sleep_chance {
+            Awareness::Asleep
+        } else {
+            Awareness::Wandering
+        };
+        monster.senses = Some(Senses {
+            sight_radius: self.sight_radius,
+            hearing: self.hearing,
+            awareness,
+            greedy: self.greedy,
+            last_turn: 0,
+        });
+        monster
+    }
+}
+
+pub(crate) fn validate_ai(ai: &Ai) -> Result<(), String> {
+    match *ai {
+        Ai::Basic => {}
+        Ai::Ranged { range, accuracy } => {
+            if range < 2 {
+                return Err("a ranged monster needs a range of 2 or more".into());
+            }
+            if !(0..=100).contains(&accuracy) {
+                return Err("accuracy goes from 0 to 100".into());
+            }
+        }
+        _ => return Err("a monster starts with the Basic or Ranged ai".into()),
+    }
+    Ok(())
+}
+
+// One entry of items.json
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ItemTemplate {
+    pub name: String,
+    pub glyph: char,
+    pub color: Color,
+    pub effect: Item,
+    // weapons and armour only, their effect is Equipment
+    #[serde(default)]
+    pub equipment: Option<Equipment>,
+    // wands and staves only, the item is not used up while charges are left
+    #[serde(default)]
+    pub charges: Option<Charges>,
+    // category of appearances.json it hides behind, known from the start without.
+    // It is drawn with the glyph of the category and the colour of its appearance.
+    #[serde(default)]
+    pub unidentified: Option<String>,
+}
+
+impl ItemTemplate {
+    pub fn validate(&self) -> Result<(), String> {
+        let positive = match self.effect {
+            Item::Heal { amount } => amount > 0,
+            Item::Lightning { damage, range } => damage > 0 && range > 0,
+            Item::Fireball { damage, radius, range } => damage > 0 && radius > 0 && range > 0,
+            Item::Confuse { range, num_turns } => range > 0 && num_turns > 0,
+            Item::Convert { range, num_turns, .. } => range > 0 && num_turns.is_none_or(|turns| turns > 0),
+            Item::Potion { status, num_turns } => {
+                status.validate()?;
+                num_turns > 0
+            }
+            Item::Equipment => true,
+            Item::Recharge { amount } => amount > 0,
+            Item::Identify => true,
+        };
+        if !positive {
+            return Err("effect parameters must be positive".into());
+        }
+        if let Some(charges) = self.charges {
+            if let Item::Equipment | Item::Recharge { .. } | Item::Identify | Item::Potion { .. } = self.effect {
+                return Err("equipment, potions and scrolls read on other items cannot have charges".into());
+            }
+            if charges.max <= 0 || !(0..=charges.max).contains(&charges.current) {
+                return Err("charges go from 0 to a positive max".into());
+            }
+        }
+        match (&self.effect, self.equipment) {
+            (Item::Equipment, Some(equipment)) => equipment.validate(),
+            (Item::Equipment, None) => Err("the Equipment effect needs an equipment entry".into()),
+            (_, Some(_)) => Err("only the Equipment effect goes with an equipment entry".into()),
+            (_, None) => Ok(()),
+        }
+    }
+
+    pub fn spawn(&self, x: i32, y: i32) -> Object {
+        let mut item = Object::new(x, y, self.glyph, self.color, &self.name, false);
+        item.item = Some(self.effect.clone());
+        item.equipment = self.equipment;
+        item.charges = self.charges;
+        item
+    }
+}
@@ -0,0 +1,100 @@
+//status.rs
+// Timed effects on a creature, the player as much as the monsters. Any
+// number of different ones can be on at once, the same one twice only
+// keeps the longest. Each creature ticks its own at the end of its turn.
+use serde::{Deserialize, Serialize};
+
+use crate::color::*;
+use crate::game::Game;
+use crate::object::Object;
+use crate::PLAYER;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StatusKind {
+    // stumbles around at random
+    Confused,
+    // loses hit points every turn
+    Poisoned { damage: i32 },
+    // acts every other turn
+    Slowed,
+    // acts twice a turn
+    Hasted,
+    // sees nothing further than next to it
+    Blinded,
+    // gets hit points back every turn
+    Regenerating { amount: i32 },
+    // cannot act at all
+    Paralysed,
+}
+
+impl StatusKind {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            StatusKind::Confused => "confused",
+            StatusKind::Poisoned { .. } => "poisoned",
+            StatusKind::Slowed => "slowed",
+            StatusKind::Hasted => "hasted",
+            StatusKind::Blinded => "blinded",
+            StatusKind::Regenerating { .. } => "regenerating",
+            StatusKind::Paralysed => "paralysed",
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            StatusKind::Poisoned { damage } if damage <= 0 => Err("poison damage must be positive".into()),
+            StatusKind::Regenerating { amount } if amount <= 0 => {
+                Err("regeneration amount must be positive".into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Same effect, whatever its strength
+    pub fn same(&self, other: &StatusKind) -> bool {
+        self.name() == other.name()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub num_turns: i32,
+}
+
+// Poison and regeneration do their work, then every effect gets one turn
+// closer to wearing off
+pub fn tick_statuses(id: usize, game: &mut Game, objects: &mut [Object]) {
+    let statuses = objects[id].statuses.clone();
+    for status in &statuses {
+        match status.kind {
+            StatusKind::Poisoned { damage } => {
+                if id == PLAYER {
+                    game.messages.add(format!("The poison burns you for {} hit points", damage), GREEN);
+                }
+                objects[id].take_damage(damage, game);
+            }
+            StatusKind::Regenerating { amount } => objects[id].heal(amount, game),
+            _ => {}
+        }
+    }
+    if !objects[id].alive {
+        return;
+    }
+    let in_view = game.fov.is_in_fov(objects[id].x, objects[id].y);
+    let object = &mut objects[id];
+    for status in object.statuses.iter_mut() {
+        status.num_turns -= 1;
+        if status.num_turns <= 0 {
+            if id == PLAYER {
+                game.messages.add(format!("You are no longer {}", status.kind.name()), LIGHT_GREY);
+            } else if in_view {
+                game.messages.add(
+                    format!("The {} is no longer {}", object.name, status.kind.name()),
+                    LIGHT_GREY,
+                );
+            }
+        }
+    }
+    object.statuses.retain(|status| status.num_turns > 0);
+}
@@ -0,0 +1,163 @@
+//vault.rs
+// Prefab rooms drawn in ASCII in data/vaults.json and stamped into solid
+// rock after a level is dug.
+//   '#' wall   '.' floor   '+' doorway, always joined to the rest of the level
+//   'M' floor with a monster   'I' floor with an item   ' ' left untouched
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::map::*;
+use crate::mapgen::{connect, walk_distances, Level};
+use crate::rng::GameRng;
+use crate::{MAP_HEIGHT, MAP_WIDTH};
+
+const MAX_VAULTS_PER_LEVEL: usize = 2;
+const PLACEMENT_TRIES: i32 = 50;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Vault {
+    pub name: String,
+    pub min_level: u32,
+    pub max_level: u32,
+    // percent chance to show up on a level between min_level and max_level
+    pub chance: u32,
+    pub rows: Vec<String>,
+}
+
+impl Vault {
+    pub fn width(&self) -> i32 {
+        self.rows.iter().map(|row| row.chars().count() as i32).max().unwrap_or(0)
+    }
+
+    pub fn height(&self) -> i32 {
+        self.rows.len() as i32
+    }
+
+    // ' ' outside the drawn rows, so rows do not need to have the same length
+    pub fn cell(&self, x: i32, y: i32) -> char {
+        if x < 0 || y < 0 {
+            return ' ';
+        }
+        self.rows
+            .get(y as usize)
+            .and_then(|row| row.chars().nth(x as usize))
+            .unwrap_or(' ')
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (i32, i32, char)> + '_ {
+        self.rows.iter().enumerate().flat_map(|(y, row)| {
+            row.chars().enumerate().map(move |(x, cell)| (x as i32, y as i32, cell))
+        })
+    }
+
+    // Where the corridor leading to a doorway starts
+    fn door_outside(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        [(0, -1), (-1, 0), (1, 0), (0, 1)]
+            .iter()
+            .map(|&(dx, dy)| (x + dx, y + dy))
+            .find(|&(x, y)| self.cell(x, y) == ' ')
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.rows.is_empty() {
+            return Err("a vault needs at least one row".into());
+        }
+        if self.min_level > self.max_level {
+            return Err(format!("min_level {} is above max_level {}", self.min_level, self.max_level));
+        }
+        if self.chance > 100 {
+            return Err(format!("chance is a percentage, got {}", self.chance));
+        }
+        if self.width() > MAP_WIDTH - 2 || self.height() > MAP_HEIGHT - 2 {
+            return Err(format!("{}x{} does not fit in the map", self.width(), self.height()));
+        }
+        for (x, y, cell) in self.cells() {
+            match cell {
+                '#' | '.' | 'M' | 'I' | ' ' => {}
+                '+' => {
+                    if self.door_outside(x, y).is_none() {
+                        return Err(format!("doorway at row {} column {} is not on the edge", y, x));
+                    }
+                }
+                other => {
+                    return Err(format!("unknown tile '{}' at row {} column {}", other, y, x));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn fits_at(&self, map: &Map, x: i32, y: i32) -> bool {
+        // the vault and a one tile margin have to be solid rock
+        self.cells().filter(|&(_, _, cell)| cell != ' ').all(|(vault_x, vault_y, _)| {
+            let (map_x, map_y) = (x + vault_x, y + vault_y);
+            map.in_bounds(map_x - 1, map_y - 1)
+                && map.in_bounds(map_x + 1, map_y + 1)
+                && map.is_blocked(map_x, map_y)
+                && map.neighbours(map_x, map_y).all(|(x, y)| map.is_blocked(x, y))
+        })
+    }
+
+    fn stamp(&self, level: &mut Level, x: i32, y: i32, rng: &mut GameRng) {
+        let reachable = walk_distances(&level.map, level.start);
+        let width = level.map.width();
+        let mut doors = vec![];
+        for (vault_x, vault_y, cell) in self.cells() {
+            let (map_x, map_y) = (x + vault_x, y + vault_y);
+            match cell {
+                '#' => level.map.set(map_x, map_y, Tile::wall()),
+                '.' => level.map.set(map_x, map_y, Tile::empty()),
+                'M' => {
+                    level.map.set(map_x, map_y, Tile::empty());
+                    level.monster_spawns.push((map_x, map_y));
+                }
+                'I' => {
+                    level.map.set(map_x, map_y, Tile::empty());
+                    level.item_spawns.push((map_x, map_y));
+                }
+                '+' => {
+                    level.map.set(map_x, map_y, Tile::empty());
+                    if let Some((outside_x, outside_y)) = self.door_outside(vault_x, vault_y) {
+                        doors.push((x + outside_x, y + outside_y));
+                    }
+                }
+                _ => {}
+            }
+        }
+        for (door_x, door_y) in doors {
+            let closest = level
+                .map
+                .positions()
+                .filter(|&(x, y)| reachable[(x + y * width) as usize].is_some())
+                .min_by_key(|&(x, y)| (x - door_x).abs() + (y - door_y).abs());
+            if let Some(closest) = closest {
+                connect((door_x, door_y), closest, &mut level.map, rng);
+            }
+        }
+    }
+}
+
+pub fn stamp_vaults(level: &mut Level, vaults: &[Vault], dungeon_level: u32, rng: &mut GameRng) {
+    let mut placed = 0;
+    for vault in vaults {
+        if placed >= MAX_VAULTS_PER_LEVEL {
+            break;
+        }
+        if dungeon_level < vault.min_level || dungeon_level > vault.max_level {
+            continue;
+        }
+        if rng.gen_range(0, 100) >= vault.chance {
+            continue;
+        }
+        for _ in 0..PLACEMENT_TRIES {
+            let x = rng.gen_range(1, MAP_WIDTH - vault.width());
+            let y = rng.gen_range(1, MAP_HEIGHT - vault.height());
+            if vault.fits_at(&level.map, x, y) {
+                vault.stamp(level, x, y, rng);
+                placed += 1;
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+// Monsters on small hand-made levels: what they do when the player cannot
+// see them, or they cannot see the player.
+use roguelite_core::color::WHITE;
+use roguelite_core::data::GameData;
+use roguelite_core::game::Game;
+use roguelite_core::map::{Map, Tile};
+use roguelite_core::object::{Ai, Awareness, Object, Stairs};
+use roguelite_core::rng::GameRng;
+use roguelite_core::rules::{new_game, take_turn, Command};
+use roguelite_core::{MAP_HEIGHT, MAP_WIDTH, PLAYER};
+
+// The player alone in a closet on the left, a long room on the right that
+// they cannot see into
+fn two_rooms() -> (Game, Vec<Object>) {
+    let data = GameData::load("../data").unwrap();
+    let (mut game, mut objects) = new_game(1, data);
+    let mut map = Map::new(MAP_WIDTH, MAP_HEIGHT, Tile::wall());
+    for x in 1..4 {
+        for y in 1..4 {
+            map.set(x, y, Tile::empty());
+        }
+    }
+    for x in 10..40 {
+        for y in 1..10 {
+            map.set(x, y, Tile::empty());
+        }
+    }
+    game.map = map;
+    objects.truncate(PLAYER + 1);
+    objects[PLAYER].set_pos(2, 2);
+    (game, objects)
+}
+
+fn monster(name: &str, x: i32, y: i32, game: &Game) -> Object {
+    let template = game.data.monsters.iter().find(|monster| monster.name == name).unwrap();
+    let mut monster = template.spawn(x, y, &mut GameRng::from_seed(0));
+    if let Some(senses) = monster.senses.as_mut() {
+        senses.awareness = Awareness::Wandering;
+    }
+    monster
+}
+
+fn wait(turns: u32, game: &mut Game, objects: &mut Vec<Object>) {
+    for _ in 0..turns {
+        take_turn(Command::Move(0, 0), game, objects);
+    }
+}
+
+#[test]
+fn greedy_monsters_guard_the_loot() {
+    let (mut game, mut objects) = two_rooms();
+    objects.push(monster("Kobold archer", 12, 5, &game));
+    let loot = game.data.items.iter().find(|item| item.name == "sword").unwrap();
+    objects.push(loot.spawn(30, 8));
+    wait(25, &mut game, &mut objects);
+    assert_eq!(objects[1].pos(), (30, 8));
+    wait(5, &mut game, &mut objects);
+    assert_eq!(objects[1].pos(), (30, 8));
+}
+
+#[test]
+fn lone_fleeing_monsters_make_for_the_stairs() {
+    let (mut game, mut objects) = two_rooms();
+    let mut orc = monster("Orc", 12, 2, &game);
+    orc.ai = Some(Ai::Fleeing {
+        previous_ai: Box::new(Ai::Basic),
+        num_turns: 0,
+    });
+    if let Some(fighter) = orc.fighter.as_mut() {
+        fighter.hp = 1;
+    }
+    objects.push(orc);
+    let mut stairs = Object::new(25, 7, '<', WHITE, "stairs down", false);
+    stairs.stairs = Some(Stairs::Down);
+    objects.push(stairs);
+    wait(14, &mut game, &mut objects);
+    assert_eq!(objects[1].pos(), (25, 7));
+    assert!(matches!(objects[1].ai, Some(Ai::Fleeing { .. })));
+}
+
+fn sharpshooter(x: i32, y: i32, game: &Game) -> Object {
+    let mut archer = monster("Kobold archer", x, y, game);
+    archer.ai = Some(Ai::Ranged {
+        range: 6,
+        accuracy: 100,
+    });
+    archer
+}
+
+#[test]
+fn shots_hit_whoever_is_in_the_way() {
+    let (mut game, mut objects) = two_rooms();
+    objects[PLAYER].set_pos(15, 5);
+    objects.push(sharpshooter(21, 5, &game));
+    objects.push(monster("Kobold archer", 18, 5, &game));
+    let player_hp = objects[PLAYER].fighter.unwrap().hp;
+    wait(2, &mut game, &mut objects);
+    let friend = objects[2].fighter.unwrap();
+    assert!(friend.hp < friend.max_hp);
+    assert!(objects[PLAYER].fighter.unwrap().hp <= player_hp);
+}
+
+#[test]
+fn unseen_shots_are_not_reported() {
+    // a fight in the far room, out of the player's sight
+    let (mut game, mut objects) = two_rooms();
+    objects.push(sharpshooter(30, 5, &game));
+    let mut rat = monster("Giant rat", 26, 5, &game);
+    rat.faction = Some("outcasts".into());
+    objects.push(rat);
+    game.messages.messages.clear();
+    wait(2, &mut game, &mut objects);
+    let rat = objects[2].fighter.unwrap();
+    assert!(rat.hp < rat.max_hp);
+    assert!(game.messages.messages.iter().all(|(message, _)| !message.contains("shoot")));
+}
+
+#[test]
+fn monsters_hear_fights_that_broke_out_after_their_turn() {
+    // the orc acts first and sleeps through the first shot, it hears it on
+    // its next turn all the same
+    let (mut game, mut objects) = two_rooms();
+    let mut orc = monster("Orc", 22, 2, &game);
+    if let Some(senses) = orc.senses.as_mut() {
+        senses.awareness = Awareness::Asleep;
+    }
+    objects.push(orc);
+    objects.push(sharpshooter(30, 5, &game));
+    let mut rat = monster("Giant rat", 26, 5, &game);
+    rat.faction = Some("outcasts".into());
+    objects.push(rat);
+    wait(2, &mut game, &mut objects);
+    assert!(objects[3].fighter.unwrap().hp < objects[3].fighter.unwrap().max_hp);
+    assert_eq!(objects[1].senses.unwrap().awareness, Awareness::Asleep);
+    wait(1, &mut game, &mut objects);
+    assert_ne!(objects[1].senses.unwrap().awareness, Awareness::Asleep);
+}
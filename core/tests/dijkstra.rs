@@ -0,0 +1,82 @@
+// Values of the Dijkstra maps on small hand-made maps, and where going
+// downhill on them leads.
+use roguelite_core::color::WHITE;
+use roguelite_core::dijkstra::{DijkstraMap, STEP_COST};
+use roguelite_core::map::{Map, Tile};
+use roguelite_core::object::Object;
+
+fn open_map(width: i32, height: i32) -> Map {
+    Map::new(width, height, Tile::empty())
+}
+
+#[test]
+fn distances_grow_from_the_goals() {
+    let mut map = open_map(5, 5);
+    map.set(4, 4, Tile::wall());
+    let dijkstra = DijkstraMap::new(&map, &[(2, 2)]);
+    assert_eq!(dijkstra.value(2, 2), Some(0));
+    assert_eq!(dijkstra.value(3, 2), Some(STEP_COST));
+    assert_eq!(dijkstra.value(3, 3), Some(STEP_COST));
+    assert_eq!(dijkstra.value(0, 0), Some(2 * STEP_COST));
+    assert_eq!(dijkstra.value(4, 4), None);
+    assert_eq!(dijkstra.value(-1, 0), None);
+
+    let dijkstra = DijkstraMap::new(&map, &[(0, 0), (4, 0)]);
+    assert_eq!(dijkstra.value(1, 0), Some(STEP_COST));
+    assert_eq!(dijkstra.value(3, 0), Some(STEP_COST));
+    assert_eq!(dijkstra.value(2, 0), Some(2 * STEP_COST));
+}
+
+#[test]
+fn walls_cut_the_map_in_two() {
+    let mut map = open_map(5, 3);
+    for y in 0..3 {
+        map.set(2, y, Tile::wall());
+    }
+    let dijkstra = DijkstraMap::new(&map, &[(0, 1)]);
+    assert_eq!(dijkstra.value(1, 1), Some(STEP_COST));
+    assert_eq!(dijkstra.value(3, 1), None);
+    assert_eq!(dijkstra.value(4, 1), None);
+}
+
+#[test]
+fn sources_keep_the_lowest_value() {
+    let map = open_map(5, 1);
+    let dijkstra = DijkstraMap::from_sources(&map, &[((0, 0), 0), ((4, 0), -30), ((2, 0), 100)]);
+    let values: Vec<_> = (0..5).map(|x| dijkstra.value(x, 0)).collect();
+    assert_eq!(values, vec![Some(0), Some(0), Some(-10), Some(-20), Some(-30)]);
+
+    // sources in walls do not count
+    let mut map = open_map(3, 1);
+    map.set(0, 0, Tile::wall());
+    let dijkstra = DijkstraMap::from_sources(&map, &[((0, 0), -100), ((2, 0), 0)]);
+    assert_eq!(dijkstra.value(1, 0), Some(STEP_COST));
+}
+
+#[test]
+fn fleeing_leads_away_from_the_goal() {
+    let map = open_map(9, 1);
+    let to_player = DijkstraMap::new(&map, &[(2, 0)]);
+    let flee = to_player.flee(&map);
+    // the further from the player, the lower
+    for x in 3..8 {
+        assert!(flee.value(x + 1, 0) < flee.value(x, 0));
+    }
+    assert!(flee.value(8, 0) < flee.value(0, 0));
+    assert_eq!(flee.downhill(4, 0, &map, &[]), Some((5, 0)));
+    assert_eq!(flee.downhill(8, 0, &map, &[]), None);
+    assert_eq!(to_player.downhill(4, 0, &map, &[]), Some((3, 0)));
+    assert_eq!(to_player.downhill(2, 0, &map, &[]), None);
+}
+
+#[test]
+fn fleeing_runs_past_the_player_out_of_a_dead_end() {
+    // a dead end on the left, a long way out on the right, the player in
+    // between but the corridor is wide enough to slip past them
+    let map = open_map(20, 3);
+    let player = Object::new(2, 1, '@', WHITE, "player", true);
+    let to_player = DijkstraMap::new(&map, &[player.pos()]);
+    let flee = to_player.flee(&map);
+    let step = flee.downhill(1, 1, &map, &[player]);
+    assert!(matches!(step, Some((2, 0)) | Some((2, 2))), "{:?}", step);
+}
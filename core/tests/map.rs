@@ -0,0 +1,63 @@
+// The Bresenham lines behind FOV, tunnels, line of sight and line of fire,
+// and the rectangles rooms are dug in.
+use roguelite_core::map::{Line, Rect};
+
+// Every step goes to one of the 8 neighbours
+fn assert_connected(points: &[(i32, i32)]) {
+    for pair in points.windows(2) {
+        let (dx, dy) = (pair[1].0 - pair[0].0, pair[1].1 - pair[0].1);
+        assert!(dx.abs() <= 1 && dy.abs() <= 1 && (dx, dy) != (0, 0), "{:?} is not a step", pair);
+    }
+}
+
+#[test]
+fn lines_include_both_ends() {
+    let points: Vec<_> = Line::new((0, 0), (5, 2)).collect();
+    assert_eq!(points.first(), Some(&(0, 0)));
+    assert_eq!(points.last(), Some(&(5, 2)));
+    assert_eq!(points.len(), 6);
+    assert_connected(&points);
+
+    let points: Vec<_> = Line::new((3, 3), (3, 3)).collect();
+    assert_eq!(points, vec![(3, 3)]);
+}
+
+#[test]
+fn straight_and_diagonal_lines() {
+    let points: Vec<_> = Line::new((2, 1), (2, 4)).collect();
+    assert_eq!(points, vec![(2, 1), (2, 2), (2, 3), (2, 4)]);
+    let points: Vec<_> = Line::new((0, 0), (3, 3)).collect();
+    assert_eq!(points, vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
+}
+
+#[test]
+fn steep_lines_take_one_row_per_step() {
+    let points: Vec<_> = Line::new((0, 0), (2, 6)).collect();
+    assert_eq!(points.len(), 7);
+    assert_connected(&points);
+    for (y, &point) in points.iter().enumerate() {
+        assert_eq!(point.1, y as i32);
+    }
+    assert_eq!(points.last(), Some(&(2, 6)));
+}
+
+#[test]
+fn reversed_lines_go_back_the_other_way() {
+    for &(from, to) in &[((0, 0), (7, 3)), ((5, 5), (1, 0)), ((-2, 3), (4, -3))] {
+        let forward: Vec<_> = Line::new(from, to).collect();
+        let backward: Vec<_> = Line::new(to, from).collect();
+        assert_eq!(backward.first(), Some(&to));
+        assert_eq!(backward.last(), Some(&from));
+        assert_eq!(forward.len(), backward.len());
+        assert_connected(&backward);
+    }
+}
+
+#[test]
+fn inner_points_leave_the_walls() {
+    let rect = Rect::new(1, 1, 4, 3);
+    let inner: Vec<_> = rect.inner_points().collect();
+    assert_eq!(inner, vec![(2, 2), (2, 3), (3, 2), (3, 3), (4, 2), (4, 3)]);
+    assert!(inner.iter().all(|&point| rect.points().any(|other| other == point)));
+    assert_eq!(Rect::new(0, 0, 1, 1).inner_points().count(), 0);
+}
@@ -0,0 +1,90 @@
+// Generates thousands of seeded levels and checks that every one of them
+// can actually be played: everything walkable from the player start.
+use roguelite_core::color::WHITE;
+use roguelite_core::data::GameData;
+use roguelite_core::map::Map;
+use roguelite_core::mapgen::{make_map, walk_distances, GeneratorKind};
+use roguelite_core::object::{Object, Stairs};
+use roguelite_core::rng::GameRng;
+use roguelite_core::PLAYER;
+
+const SEEDS_PER_LEVEL: u64 = 250;
+// first levels of every generator, plus a few of the deeper random ones
+const LEVELS: [u32; 8] = [1, 3, 5, 7, 9, 10, 11, 12];
+
+// The real content, vaults included
+fn load_data() -> GameData {
+    GameData::load("../data").unwrap()
+}
+
+// make_map only needs the player, to put them at the start
+fn generate(seed: u64, dungeon_level: u32, data: &GameData) -> (Map, Vec<Object>) {
+    let mut objects = vec![Object::new(0, 0, '@', WHITE, "player", true)];
+    let mut rng = GameRng::from_seed(seed);
+    let map = make_map(&mut objects, &mut rng, dungeon_level, data);
+    (map, objects)
+}
+
+fn check_level(seed: u64, dungeon_level: u32, data: &GameData) {
+    let (map, objects) = generate(seed, dungeon_level, data);
+    let context = format!("seed {} level {} ({:?})", seed, dungeon_level, GeneratorKind::for_level(dungeon_level));
+    let start = objects[PLAYER].pos();
+    assert!(!map.is_blocked(start.0, start.1), "{}: player starts in a wall", context);
+
+    let reachable = walk_distances(&map, start);
+    let is_reachable = |(x, y): (i32, i32)| reachable[(x + y * map.width()) as usize].is_some();
+    for (x, y) in map.positions() {
+        assert!(
+            map.is_blocked(x, y) || is_reachable((x, y)),
+            "{}: floor at {:?} cannot be reached",
+            context,
+            (x, y)
+        );
+    }
+
+    let stairs = |kind: Stairs| objects.iter().filter(|object| object.stairs == Some(kind)).collect::<Vec<_>>();
+    let down = stairs(Stairs::Down);
+    assert_eq!(down.len(), 1, "{}: expected one way down", context);
+    assert_ne!(down[0].pos(), start, "{}: stairs under the player", context);
+    assert!(is_reachable(down[0].pos()), "{}: stairs cannot be reached", context);
+    let up = stairs(Stairs::Up);
+    if dungeon_level > 1 {
+        assert_eq!(up.len(), 1, "{}: expected one way up", context);
+        assert_eq!(up[0].pos(), start, "{}: player does not arrive on the way up", context);
+    } else {
+        assert!(up.is_empty(), "{}: no way up from the first level", context);
+    }
+
+    for (id, object) in objects.iter().enumerate() {
+        assert!(map.in_bounds(object.x, object.y), "{}: {} out of the map", context, object.name);
+        assert!(!map.is_blocked(object.x, object.y), "{}: {} inside a wall", context, object.name);
+        let crowded = objects[..id].iter().any(|other| other.blocks && object.blocks && other.pos() == object.pos());
+        assert!(!crowded, "{}: {} on an occupied tile at {:?}", context, object.name, object.pos());
+        if object.item.is_some() {
+            assert!(is_reachable(object.pos()), "{}: {} cannot be reached", context, object.name);
+        }
+    }
+}
+
+#[test]
+fn generated_levels_are_playable() {
+    let data = load_data();
+    for &dungeon_level in &LEVELS {
+        for seed in 0..SEEDS_PER_LEVEL {
+            check_level(seed, dungeon_level, &data);
+        }
+    }
+}
+
+#[test]
+fn same_seed_same_level() {
+    let data = load_data();
+    for seed in 0..20 {
+        let (first_map, first_objects) = generate(seed, 5, &data);
+        let (second_map, second_objects) = generate(seed, 5, &data);
+        assert_eq!(
+            serde_json::to_string(&(&first_map, &first_objects)).unwrap(),
+            serde_json::to_string(&(&second_map, &second_objects)).unwrap(),
+        );
+    }
+}
@@ -0,0 +1,73 @@
+// A* around walls, and other creatures as soft obstacles: worth going
+// around in the open, queued behind in a corridor.
+use roguelite_core::color::WHITE;
+use roguelite_core::map::{Map, Tile};
+use roguelite_core::object::Object;
+use roguelite_core::pathfinding::{astar, Path};
+
+fn open_map(width: i32, height: i32) -> Map {
+    Map::new(width, height, Tile::empty())
+}
+
+fn blocker(x: i32, y: i32) -> Object {
+    Object::new(x, y, 'o', WHITE, "blocker", true)
+}
+
+#[test]
+fn astar_goes_around_walls() {
+    // a wall across the map, open at the bottom only
+    let mut map = open_map(10, 5);
+    for y in 0..4 {
+        map.set(5, y, Tile::wall());
+    }
+    let steps = astar(&map, (1, 1), (8, 1), |_, _| 1).unwrap();
+    assert_eq!(steps.last(), Some(&(8, 1)));
+    assert!(steps.iter().all(|&(x, y)| !map.is_blocked(x, y)));
+    assert!(steps.contains(&(5, 4)));
+    let mut previous = (1, 1);
+    for &step in &steps {
+        assert!((step.0 - previous.0).abs() <= 1 && (step.1 - previous.1).abs() <= 1);
+        previous = step;
+    }
+
+    map.set(5, 4, Tile::wall());
+    assert_eq!(astar(&map, (1, 1), (8, 1), |_, _| 1), None);
+    assert_eq!(astar(&map, (1, 1), (5, 2), |_, _| 1), None);
+    assert_eq!(astar(&map, (1, 1), (1, 1), |_, _| 1), Some(vec![]));
+}
+
+#[test]
+fn astar_takes_the_cheapest_way() {
+    let map = open_map(5, 3);
+    let steps = astar(&map, (0, 1), (4, 1), |x, y| if (x, y) == (2, 1) { 10 } else { 1 }).unwrap();
+    assert_eq!(steps.len(), 4);
+    assert!(!steps.contains(&(2, 1)));
+}
+
+#[test]
+fn paths_go_around_creatures_in_the_open() {
+    let map = open_map(5, 3);
+    let objects = vec![blocker(2, 1)];
+    let path = Path::find((0, 1), (4, 1), &map, &objects).unwrap();
+    let mut steps = vec![];
+    let mut walker = path.clone();
+    while let Some(step) = walker.next_step() {
+        steps.push(step);
+        walker.advance();
+    }
+    assert_eq!(steps.len(), 4);
+    assert!(!steps.contains(&(2, 1)));
+}
+
+#[test]
+fn paths_queue_behind_creatures_in_corridors() {
+    let map = open_map(5, 1);
+    let mut objects = vec![blocker(2, 0)];
+    let path = Path::find((0, 0), (4, 0), &map, &objects).unwrap();
+    assert_eq!(path.next_step(), Some((1, 0)));
+    assert!(path.is_valid((0, 0), (4, 0), &objects));
+    assert!(!path.is_valid((0, 0), (3, 0), &objects));
+    // the way cleared up, worth looking again
+    objects[0].set_pos(4, 0);
+    assert!(!path.is_valid((0, 0), (4, 0), &objects));
+}
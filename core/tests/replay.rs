@@ -0,0 +1,70 @@
+// Records a run the way the front end does, then plays the replay back from
+// scratch and from a file: the games must come out the same.
+use std::env;
+
+use roguelite_core::data::GameData;
+use roguelite_core::game::Game;
+use roguelite_core::object::Object;
+use roguelite_core::replay::Replay;
+use roguelite_core::rules::{new_game, take_turn, Command};
+
+const SEED: u64 = 7;
+
+fn commands() -> Vec<Command> {
+    let moves = [(1, 0), (0, 1), (-1, 0), (0, -1), (1, 1), (-1, -1), (1, -1), (-1, 1)];
+    (0..150)
+        .map(|turn| match turn % 10 {
+            3 => Command::PickUp,
+            7 => Command::Descend,
+            _ => {
+                let (dx, dy) = moves[(turn * 5 + turn / 4) % moves.len()];
+                Command::Move(dx, dy)
+            }
+        })
+        .collect()
+}
+
+fn snapshot(game: &Game, objects: &[Object]) -> String {
+    serde_json::to_string(&(game, objects)).unwrap()
+}
+
+// The live game, and the replay recorded along the way
+fn record(data: &GameData) -> (String, Replay) {
+    let (mut game, mut objects) = new_game(SEED, data.clone());
+    let mut replay = Replay::new(SEED);
+    for command in commands() {
+        replay.record(command);
+        take_turn(command, &mut game, &mut objects);
+    }
+    (snapshot(&game, &objects), replay)
+}
+
+fn play_back(replay: &Replay, data: &GameData) -> String {
+    let (mut game, mut objects) = replay.start(data.clone());
+    let mut index = 0;
+    while replay.step(index, &mut game, &mut objects) {
+        index += 1;
+    }
+    assert_eq!(index, replay.commands.len());
+    snapshot(&game, &objects)
+}
+
+#[test]
+fn replay_rebuilds_the_live_game() {
+    let data = GameData::load("../data").unwrap();
+    let (live, replay) = record(&data);
+    assert_eq!(play_back(&replay, &data), live);
+}
+
+#[test]
+fn replay_files_play_back_the_same() {
+    let data = GameData::load("../data").unwrap();
+    let (live, replay) = record(&data);
+    let path = env::temp_dir().join(format!("roguelite-replay-{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+    replay.save(path).unwrap();
+    let loaded = Replay::load(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(loaded, replay);
+    assert_eq!(play_back(&loaded, &data), live);
+}
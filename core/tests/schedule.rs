@@ -0,0 +1,102 @@
+// Counts who gets to act, and how often, when creatures of different speeds
+// share a level, and replays whole games to check they never diverge.
+use roguelite_core::color::WHITE;
+use roguelite_core::data::GameData;
+use roguelite_core::object::{Ai, Object};
+use roguelite_core::rules::{new_game, take_turn, Command};
+use roguelite_core::schedule::{next_actor, ACTION_COST, NORMAL_SPEED};
+use roguelite_core::status::StatusKind;
+use roguelite_core::PLAYER;
+
+const PLAYER_TURNS: usize = 20;
+
+fn creature(speed: i32) -> Object {
+    let mut object = Object::new(0, 0, 'c', WHITE, "creature", true);
+    object.alive = true;
+    object.ai = Some(Ai::Basic);
+    object.speed = speed;
+    object
+}
+
+// How many actions each object gets while the player takes PLAYER_TURNS,
+// everybody ready to go at the start
+fn count_actions(mut objects: Vec<Object>) -> Vec<usize> {
+    for object in objects.iter_mut() {
+        object.energy = ACTION_COST;
+    }
+    let mut actions = vec![0; objects.len()];
+    loop {
+        let id = next_actor(&mut objects);
+        if id == PLAYER && actions[PLAYER] == PLAYER_TURNS {
+            return actions;
+        }
+        objects[id].energy -= ACTION_COST;
+        actions[id] += 1;
+    }
+}
+
+fn player() -> Object {
+    let mut player = Object::new(0, 0, '@', WHITE, "player", true);
+    player.alive = true;
+    player
+}
+
+#[test]
+fn speed_sets_how_often_creatures_act() {
+    let actions = count_actions(vec![player(), creature(NORMAL_SPEED), creature(200), creature(50)]);
+    assert_eq!(actions, vec![20, 20, 40, 10]);
+}
+
+#[test]
+fn player_acts_first_on_ties() {
+    let mut objects = vec![player(), creature(NORMAL_SPEED)];
+    objects[PLAYER].energy = ACTION_COST;
+    objects[1].energy = ACTION_COST;
+    assert_eq!(next_actor(&mut objects), PLAYER);
+}
+
+#[test]
+fn haste_and_slowness_change_the_pace() {
+    let mut hasted = creature(NORMAL_SPEED);
+    hasted.add_status(StatusKind::Hasted, 1000);
+    let mut slowed = creature(NORMAL_SPEED);
+    slowed.add_status(StatusKind::Slowed, 1000);
+    let mut both = creature(NORMAL_SPEED);
+    both.add_status(StatusKind::Hasted, 1000);
+    both.add_status(StatusKind::Slowed, 1000);
+    let actions = count_actions(vec![player(), hasted, slowed, both]);
+    assert_eq!(actions, vec![20, 40, 10, 20]);
+
+    let mut fast_player = player();
+    fast_player.add_status(StatusKind::Hasted, 1000);
+    let actions = count_actions(vec![fast_player, creature(NORMAL_SPEED)]);
+    assert_eq!(actions, vec![20, 10]);
+}
+
+#[test]
+fn dead_and_idle_objects_never_act() {
+    let mut dead = creature(NORMAL_SPEED);
+    dead.alive = false;
+    let mut item = creature(NORMAL_SPEED);
+    item.ai = None;
+    let actions = count_actions(vec![player(), dead, item]);
+    assert_eq!(actions, vec![20, 0, 0]);
+}
+
+// Same seed and same commands, same game
+#[test]
+fn games_replay_the_same() {
+    let data = GameData::load("../data").unwrap();
+    let moves = [(1, 0), (0, 1), (-1, 0), (0, -1), (1, 1), (-1, -1), (1, -1), (-1, 1)];
+    let play = || {
+        let (mut game, mut objects) = new_game(42, data.clone());
+        for turn in 0..200 {
+            let (dx, dy) = moves[(turn * 7 + turn / 3) % moves.len()];
+            take_turn(Command::Move(dx, dy), &mut game, &mut objects);
+        }
+        let positions: Vec<_> = objects.iter().map(|object| (object.pos(), object.energy)).collect();
+        let messages: Vec<_> = game.messages.iter().map(|(message, _)| message.clone()).collect();
+        (positions, messages)
+    };
+    assert_eq!(play(), play());
+}
@@ -1,168 +0,0 @@
-// Object.rs
-use tcod::colors::*;
-use tcod::console::*;
-use serde::{Deserialize, Serialize};
-use crate::game::Game;
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Object {
-    pub x: i32,
-    pub y: i32,
-    pub char: char,
-    pub color: Color,
-    pub name: String, 
-    pub blocks: bool,
-    pub alive: bool,
-    pub fighter: Option<Fighter>,
-    pub ai: Option<Ai>,
-    pub item: Option<Item>,
-}
-
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
-pub enum Item {
-    Heal,
-    Lightning,
-    Confuse,
-}
-
-pub enum UseResult {
-    UsedUp,
-    Cancelled,
-}
-
-impl Object {
-    pub fn new(x: i32, y: i32, char: char, color: Color, name: &str, blocks: bool) -> Self {
-        Object {
-            x: x,
-            y: y,
-            char: char,
-            color: color,
-            name: name.into(),
-            blocks: blocks,
-            alive: false,
-            fighter: None,
-            ai: None,
-            item: None,
-        }
-    }
-
-    
-    pub fn draw(&self, con: &mut dyn Console) {
-        con.set_default_foreground(self.color);
-        con.put_char(self.x, self.y, self.char, BackgroundFlag::None);
-    }
-
-    pub fn pos(&self) -> (i32, i32) {
-        (self.x, self.y)
-    }
-
-    pub fn set_pos(&mut self, x: i32, y: i32) {
-        self.x = x;
-        self.y = y;
-    }
-
-    pub fn distance_to(&self, other: &Object) -> f32 {
-        let dx = other.x - self.x;
-        let dy = other.y - self.y;
-        ((dx.pow(2) + dy.pow(2)) as f32).sqrt()
-    }
-
-    pub fn take_damage(&mut self, damage: i32, game: &mut Game) {
-        if let Some(fighter) = self.fighter.as_mut() {
-            if damage > 0 {
-                fighter.hp -= damage;
-            }
-        }
-        if let Some(fighter) = self.fighter {
-            if fighter.hp <= 0 {
-                self.alive = false;
-                fighter.on_death.callback(self, game);
-            }
-        }
-    }
-
-    pub fn attack(&mut self, target: &mut Object, game: &mut Game) {
-        let damage = self.fighter.map_or(0, |f| f.power) - target.fighter.map_or(0, |f| f.defense);
-        if damage > 0 {
-            game.messages.add(
-                format!(
-                    "{} attacks {} for {} hit points.",
-                    self.name, target.name, damage
-                    ),
-                    WHITE
-            );
-            target.take_damage(damage, game);
-        } else {
-            game.messages.add(
-                format!(
-                    "{} attacks {} but it has not effects!",
-                    self.name, target.name
-                    ),
-                    WHITE,
-            );
-
-        }
-    }
-
-    pub fn heal(&mut self, amount: i32) {
-        if let Some(ref mut fighter) = self.fighter {
-            fighter.hp += amount;
-            if fighter.hp > fighter.max_hp {
-                fighter.hp = fighter.max_hp;
-            }
-        }
-    }
-}
-
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
-pub struct Fighter {
-    pub max_hp: i32,
-    pub hp: i32,
-    pub defense: i32,
-    pub power: i32,
-    pub on_death: DeathCallBack,
-}
-
-// define AI type for monster
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub enum Ai {
-    Basic,
-    Confused {
-        previous_ai: Box<Ai>,
-        num_turns: i32,
-    },
-}
-
-
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
-pub enum DeathCallBack {
-    Player, 
-    Monster,
-}
-
-impl DeathCallBack {
-    fn callback(self, object: &mut Object, game: &mut Game) {
-        use DeathCallBack::*;
-        let callback = match self {
-            Player => player_death,
-            Monster => monster_death,
-        };
-        callback(object, game);
-    }
-}
-
-fn player_death(player: &mut Object, game: &mut Game) {
-    game.messages.add("You died!", RED);
-    player.char = '%';
-    player.color = DARK_RED;
-}
-
-fn monster_death(monster: &mut Object, game: &mut Game) {
-    game.messages.add(format!("{} is dead", monster.name), GREEN);
-    monster.char = '%';
-    monster.color = DARK_RED;
-    monster.blocks = false;
-    monster.fighter = None;
-    monster.ai = None;
-    monster.name = format!("Remains of {}", monster.name);
-}